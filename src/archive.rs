@@ -0,0 +1,779 @@
+//! Readers and writers of the archives which contain BEAM files.
+//!
+//! Only the subset of the formats produced by the Erlang/OTP tools is supported
+//! (e.g., no ZIP64 nor encryption).
+//!
+//! The methods without `_with_limits` impose no limits other than the sizes recorded in the archives.
+//! With `DecodeLimits`, `max_file_size` also applies to each member of a tarball and each uncompressed
+//! entry of a ZIP archive, and the BEAM files are decoded within the limits.
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str;
+use libflate::{deflate, gzip};
+
+use crate::chunk::{aux, Chunk};
+use crate::io::{Read, Write};
+use crate::io_ext::ReadExt;
+use crate::{BeamFile, DecodeLimits, Error, Limit, Result};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+const FLAG_ENCRYPTED: u16 = 1;
+const FLAG_UTF8: u16 = 1 << 11;
+
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// A ZIP archive held in memory.
+///
+/// ```
+/// use beam_file::archive::{ZipArchive, ZipWriter};
+///
+/// let mut writer = ZipWriter::new(Vec::new());
+/// writer.add("foo/ebin/foo.app", b"{application, foo, []}.").unwrap();
+/// let bytes = writer.finish().unwrap();
+///
+/// let archive = ZipArchive::new(&bytes).unwrap();
+/// let entry = archive.entry("foo/ebin/foo.app").unwrap();
+/// assert_eq!(b"{application, foo, []}.", &archive.read(entry).unwrap()[..]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZipArchive<'a> {
+    bytes: &'a [u8],
+    base: usize,
+    entries: Vec<ZipEntry>,
+}
+impl<'a> ZipArchive<'a> {
+    /// Parses the central directory of the archive in `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        let eocd = find_end_of_central_directory(bytes)?;
+        let mut r = SliceReader::new(bytes, eocd + 10);
+        let count = r.u16()?;
+        let cd_size = r.u32()? as usize;
+        let cd_offset = r.u32()? as usize;
+
+        // Data preceding the archive (e.g., an escript header) shifts the offsets
+        let base = eocd
+            .checked_sub(cd_size)
+            .and_then(|n| n.checked_sub(cd_offset))
+            .ok_or(Error::InvalidArchive {
+                reason: "central directory out of range",
+            })?;
+
+        let mut r = SliceReader::new(bytes, base + cd_offset);
+        let mut entries = Vec::with_capacity(aux::capacity(usize::from(count)));
+        for _ in 0..count {
+            if r.u32()? != CENTRAL_HEADER_SIGNATURE {
+                return Err(Error::InvalidArchive {
+                    reason: "bad central directory header",
+                });
+            }
+            r.skip(4)?; // The versions
+            let flags = r.u16()?;
+            let method = r.u16()?;
+            r.skip(4)?; // The modification time
+            let crc32 = r.u32()?;
+            let compressed_size = r.u32()?;
+            let uncompressed_size = r.u32()?;
+            let name_len = usize::from(r.u16()?);
+            let extra_len = usize::from(r.u16()?);
+            let comment_len = usize::from(r.u16()?);
+            r.skip(8)?; // The disk number and attributes
+            let local_header_offset = r.u32()?;
+            let name = String::from_utf8(r.bytes(name_len)?.to_vec())
+                .map_err(|e| Error::from(e.utf8_error()))?;
+            r.skip(extra_len + comment_len)?;
+            entries.push(ZipEntry {
+                name,
+                flags,
+                method,
+                crc32,
+                compressed_size,
+                uncompressed_size,
+                local_header_offset,
+            });
+        }
+        Ok(ZipArchive {
+            bytes,
+            base,
+            entries,
+        })
+    }
+
+    /// Returns the entries in the order of the central directory.
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry named `name`.
+    pub fn entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Reads the uncompressed data of `entry`.
+    pub fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>> {
+        self.read_with_limits(entry, &DecodeLimits::default())
+    }
+
+    /// Reads the uncompressed data of `entry` if its size is within `limits.max_file_size`.
+    pub fn read_with_limits(&self, entry: &ZipEntry, limits: &DecodeLimits) -> Result<Vec<u8>> {
+        limits.check(Limit::FileSize, u64::from(entry.uncompressed_size))?;
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            return Err(Error::InvalidArchive {
+                reason: "encrypted entry",
+            });
+        }
+        let mut r = SliceReader::new(self.bytes, self.base + entry.local_header_offset as usize);
+        if r.u32()? != LOCAL_HEADER_SIGNATURE {
+            return Err(Error::InvalidArchive {
+                reason: "bad local file header",
+            });
+        }
+        r.skip(22)?; // The fields duplicated in the central directory
+        let name_len = usize::from(r.u16()?);
+        let extra_len = usize::from(r.u16()?);
+        r.skip(name_len + extra_len)?;
+        let compressed = r.bytes(entry.compressed_size as usize)?;
+
+        let size = entry.uncompressed_size as usize;
+        let data = match entry.method {
+            METHOD_STORED => compressed.to_vec(),
+            METHOD_DEFLATED => {
+                // One more byte than expected is read to detect the size mismatch
+                let mut data = Vec::with_capacity(aux::capacity(size));
+                deflate::Decoder::new(compressed)
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut data)?;
+                data
+            }
+            method => return Err(Error::UnsupportedCompression { method }),
+        };
+        if data.len() != size {
+            return Err(Error::InvalidArchive {
+                reason: "uncompressed size mismatch",
+            });
+        }
+        let crc32 = crc32fast::hash(&data);
+        if crc32 != entry.crc32 {
+            return Err(Error::ChecksumMismatch {
+                expected: entry.crc32,
+                actual: crc32,
+            });
+        }
+        Ok(data)
+    }
+}
+
+/// An application archive (`.ez`) which the code server can load modules from.
+///
+/// The modules are the entries named `App-Vsn/ebin/Module.beam`.
+/// Nothing is extracted to disk.
+///
+/// ```
+/// use beam_file::archive::EzArchive;
+/// use beam_file::chunk::StandardChunk;
+///
+/// let bytes = std::fs::read("tests/testdata/foo-1.0.ez").unwrap();
+/// let archive = EzArchive::new(&bytes).unwrap();
+/// let modules = archive.modules::<StandardChunk>().unwrap();
+/// assert_eq!("foo-1.0/ebin/test.beam", modules[0].path);
+///
+/// let app = archive.app_file().unwrap().unwrap();
+/// assert_eq!("foo-1.0/ebin/foo.app", app.path);
+/// assert!(app.data.starts_with(b"{application,foo,"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EzArchive<'a> {
+    zip: ZipArchive<'a>,
+}
+impl<'a> EzArchive<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        Ok(EzArchive {
+            zip: ZipArchive::new(bytes)?,
+        })
+    }
+
+    /// Returns the underlying ZIP archive.
+    pub fn zip(&self) -> &ZipArchive<'a> {
+        &self.zip
+    }
+
+    /// Returns the `App-Vsn/ebin/*.beam` entries.
+    pub fn beam_entries(&self) -> impl Iterator<Item = &ZipEntry> {
+        self.ebin_entries(".beam")
+    }
+
+    /// Returns the `App-Vsn/ebin/*.app` entry.
+    pub fn app_entry(&self) -> Option<&ZipEntry> {
+        self.ebin_entries(".app").next()
+    }
+
+    /// Reads the `.app` file of the application.
+    pub fn app_file(&self) -> Result<Option<ArchivedFile>> {
+        self.app_entry()
+            .map(|entry| {
+                let data = with_entry_context(entry, || self.zip.read(entry))?;
+                Ok(ArchivedFile {
+                    path: entry.name.clone(),
+                    data,
+                })
+            })
+            .transpose()
+    }
+
+    /// Decodes the BEAM files in the order of the entries.
+    pub fn modules<C: Chunk>(&self) -> Result<Vec<ArchivedBeam<C>>> {
+        self.modules_with_limits(&DecodeLimits::default())
+    }
+
+    /// Decodes the BEAM files in the order of the entries within `limits`.
+    pub fn modules_with_limits<C: Chunk>(
+        &self,
+        limits: &DecodeLimits,
+    ) -> Result<Vec<ArchivedBeam<C>>> {
+        self.beam_entries()
+            .map(|entry| {
+                let beam = with_entry_context(entry, || {
+                    let data = self.zip.read_with_limits(entry, limits)?;
+                    BeamFile::from_reader_with_limits(&data[..], limits)
+                })?;
+                Ok(ArchivedBeam {
+                    path: entry.name.clone(),
+                    beam,
+                })
+            })
+            .collect()
+    }
+
+    fn ebin_entries<'b>(&'b self, extension: &'b str) -> impl Iterator<Item = &'b ZipEntry> {
+        self.zip.entries().iter().filter(move |e| {
+            let mut components = e.name.split('/');
+            matches!(
+                (components.next(), components.next(), components.next(), components.next()),
+                (Some(app), Some("ebin"), Some(file), None)
+                    if !app.is_empty() && has_extension(file, extension)
+            )
+        })
+    }
+}
+
+/// A BEAM file decoded from an archive.
+#[derive(Debug)]
+pub struct ArchivedBeam<C> {
+    /// The path of the file in the archive.
+    pub path: String,
+
+    /// The BEAM file.
+    pub beam: BeamFile<C>,
+}
+
+/// A file read from an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedFile {
+    /// The path of the file in the archive.
+    pub path: String,
+
+    /// The contents of the file.
+    pub data: Vec<u8>,
+}
+
+/// A reader of the release tarballs (`.tar.gz`) made by `systools:make_tar/2`, relx or `mix release`.
+///
+/// The tarball is decompressed and decoded as a stream, yielding the `lib/App-Vsn/ebin/*.beam` files,
+/// the `releases/Vsn/*.rel` files and the BEAM files in the `lib/*.ez` archives.
+/// The other members are skipped.
+///
+/// ```
+/// use beam_file::archive::{ReleaseItem, ReleaseReader};
+/// use beam_file::chunk::StandardChunk;
+///
+/// let f = std::fs::File::open("tests/testdata/foo-1.0.tar.gz").unwrap();
+/// let mut paths = Vec::new();
+/// for item in ReleaseReader::<_, StandardChunk>::new(f).unwrap() {
+///     match item.unwrap() {
+///         ReleaseItem::Beam(beam) => paths.push(beam.path),
+///         ReleaseItem::Rel(rel) => paths.push(rel.path),
+///     }
+/// }
+/// assert_eq!("lib/test-1.0/ebin/test.beam", paths[0]);
+/// assert_eq!("lib/foo-1.0.ez/foo-1.0/ebin/test.beam", paths[2]);
+/// assert_eq!("releases/1.0/foo.rel", paths[4]);
+/// ```
+pub struct ReleaseReader<R, C> {
+    tar: TarReader<gzip::Decoder<R>>,
+    pending: VecDeque<Result<ReleaseItem<C>>>,
+    done: bool,
+    limits: DecodeLimits,
+}
+impl<R: Read, C: Chunk> ReleaseReader<R, C> {
+    /// Reads the gzip header from `reader`.
+    pub fn new(reader: R) -> Result<Self> {
+        Self::with_limits(reader, DecodeLimits::default())
+    }
+
+    /// Reads the gzip header from `reader`, and reads the members within `limits` afterwards.
+    ///
+    /// A member which exceeds `limits.max_file_size` is reported as an error
+    /// and the following members are read as usual.
+    pub fn with_limits(reader: R, limits: DecodeLimits) -> Result<Self> {
+        Ok(ReleaseReader {
+            tar: TarReader::new(gzip::Decoder::new(reader)?),
+            pending: VecDeque::new(),
+            done: false,
+            limits,
+        })
+    }
+
+    /// Reads the next member of interest, returning `false` at the end of the tarball.
+    fn read_member(&mut self) -> Result<bool> {
+        let (path, size, kind) = loop {
+            let Some((path, size)) = self.tar.next_file()? else {
+                return Ok(false);
+            };
+            match member_kind(&path) {
+                Some(kind) => break (path, size, kind),
+                None => self.tar.skip_data(size)?,
+            }
+        };
+        if let Err(e) = self.limits.check(Limit::FileSize, size) {
+            self.tar.skip_data(size)?;
+            self.pending.push_back(Err(Error::InArchiveEntry {
+                name: path,
+                source: Box::new(e),
+            }));
+            return Ok(true);
+        }
+        let data = self.tar.read_data(size)?;
+        match kind {
+            MemberKind::Beam => {
+                let beam =
+                    BeamFile::from_reader_with_limits(&data[..], &self.limits).map_err(|e| {
+                        Error::InArchiveEntry {
+                            name: path.clone(),
+                            source: Box::new(e),
+                        }
+                    });
+                self.pending
+                    .push_back(beam.map(|beam| ReleaseItem::Beam(ArchivedBeam { path, beam })));
+            }
+            MemberKind::Rel => {
+                self.pending
+                    .push_back(Ok(ReleaseItem::Rel(ArchivedFile { path, data })));
+            }
+            MemberKind::Ez => {
+                let archive = match EzArchive::new(&data) {
+                    Ok(archive) => archive,
+                    Err(e) => {
+                        self.pending.push_back(Err(Error::InArchiveEntry {
+                            name: path,
+                            source: Box::new(e),
+                        }));
+                        return Ok(true);
+                    }
+                };
+                for entry in archive.beam_entries() {
+                    let path = alloc::format!("{}/{}", path, entry.name);
+                    let beam = archive
+                        .zip()
+                        .read_with_limits(entry, &self.limits)
+                        .and_then(|data| BeamFile::from_reader_with_limits(&data[..], &self.limits))
+                        .map_err(|e| Error::InArchiveEntry {
+                            name: path.clone(),
+                            source: Box::new(e),
+                        });
+                    self.pending
+                        .push_back(beam.map(|beam| ReleaseItem::Beam(ArchivedBeam { path, beam })));
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+impl<R: Read, C: Chunk> Iterator for ReleaseReader<R, C> {
+    type Item = Result<ReleaseItem<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+            match self.read_member() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    // The position in the stream is unknown after an error in the tarball itself
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// An item read by `ReleaseReader`.
+#[derive(Debug)]
+pub enum ReleaseItem<C> {
+    /// A BEAM file, whose path in a `.ez` archive is appended to the path of the archive
+    /// (e.g., `"lib/foo-1.0.ez/foo-1.0/ebin/foo.beam"`).
+    Beam(ArchivedBeam<C>),
+
+    /// A release resource file.
+    Rel(ArchivedFile),
+}
+
+/// An entry in a `ZipArchive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    /// The path of the entry (e.g., `"foo-1.0/ebin/foo.beam"`).
+    pub name: String,
+
+    /// The compression method (`0` for stored, `8` for deflated).
+    pub method: u16,
+
+    /// The size of the uncompressed data in bytes.
+    pub uncompressed_size: u32,
+
+    flags: u16,
+    crc32: u32,
+    compressed_size: u32,
+    local_header_offset: u32,
+}
+
+/// A writer of ZIP archives whose entries are deflated.
+#[derive(Debug)]
+pub struct ZipWriter<W> {
+    writer: W,
+    offset: u64,
+    central_directory: Vec<u8>,
+    count: u16,
+}
+impl<W: Write> ZipWriter<W> {
+    pub fn new(writer: W) -> Self {
+        ZipWriter {
+            writer,
+            offset: 0,
+            central_directory: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Appends an entry named `name`.
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let mut encoder = deflate::Encoder::new(Vec::new());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish().into_result()?;
+
+        let crc32 = crc32fast::hash(data);
+        let compressed_size = aux::to_u32("zip entry size", compressed.len())?;
+        let uncompressed_size = aux::to_u32("zip entry size", data.len())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| Error::TooLarge {
+            what: "zip entry name",
+            size: name.len() as u64,
+        })?;
+        let local_header_offset = u32::try_from(self.offset).map_err(|_| Error::TooLarge {
+            what: "zip archive",
+            size: self.offset,
+        })?;
+        self.count = self.count.checked_add(1).ok_or(Error::TooLarge {
+            what: "zip entry count",
+            size: u64::from(self.count) + 1,
+        })?;
+
+        // The fields shared by the local file header and the central directory header
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20u16.to_le_bytes()); // The version needed to extract
+        common.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        common.extend_from_slice(&METHOD_DEFLATED.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // 00:00:00
+        common.extend_from_slice(&0x21u16.to_le_bytes()); // 1980-01-01
+        common.extend_from_slice(&crc32.to_le_bytes());
+        common.extend_from_slice(&compressed_size.to_le_bytes());
+        common.extend_from_slice(&uncompressed_size.to_le_bytes());
+        common.extend_from_slice(&name_len.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // The extra field length
+
+        self.writer
+            .write_all(&LOCAL_HEADER_SIGNATURE.to_le_bytes())?;
+        self.writer.write_all(&common)?;
+        self.writer.write_all(name.as_bytes())?;
+        self.writer.write_all(&compressed)?;
+        self.offset += 30 + u64::from(name_len) + u64::from(compressed_size);
+
+        let cd = &mut self.central_directory;
+        cd.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        cd.extend_from_slice(&20u16.to_le_bytes()); // The version made by
+        cd.extend_from_slice(&common);
+        cd.extend_from_slice(&[0; 10]); // The comment length, disk number and attributes
+        cd.extend_from_slice(&local_header_offset.to_le_bytes());
+        cd.extend_from_slice(name.as_bytes());
+        Ok(())
+    }
+
+    /// Writes the central directory and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        let cd_offset = aux::to_u32("zip archive", self.offset as usize)?;
+        let cd_size = aux::to_u32("zip central directory", self.central_directory.len())?;
+        self.writer.write_all(&self.central_directory)?;
+        self.writer
+            .write_all(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes())?;
+        self.writer.write_all(&[0; 4])?; // The disk numbers
+        self.writer.write_all(&self.count.to_le_bytes())?;
+        self.writer.write_all(&self.count.to_le_bytes())?;
+        self.writer.write_all(&cd_size.to_le_bytes())?;
+        self.writer.write_all(&cd_offset.to_le_bytes())?;
+        self.writer.write_all(&0u16.to_le_bytes())?; // The comment length
+        Ok(self.writer)
+    }
+}
+
+enum MemberKind {
+    Beam,
+    Rel,
+    Ez,
+}
+
+fn member_kind(path: &str) -> Option<MemberKind> {
+    let components = path.split('/').collect::<Vec<_>>();
+    match components[..] {
+        ["lib", app, "ebin", file] if !app.is_empty() && has_extension(file, ".beam") => {
+            Some(MemberKind::Beam)
+        }
+        ["releases", vsn, file] if !vsn.is_empty() && has_extension(file, ".rel") => {
+            Some(MemberKind::Rel)
+        }
+        ["lib", file] if has_extension(file, ".ez") => Some(MemberKind::Ez),
+        _ => None,
+    }
+}
+
+fn has_extension(file: &str, extension: &str) -> bool {
+    file.len() > extension.len() && file.ends_with(extension)
+}
+
+/// A reader of the ustar format, including the long names of GNU and pax.
+struct TarReader<R> {
+    reader: R,
+}
+impl<R: Read> TarReader<R> {
+    fn new(reader: R) -> Self {
+        TarReader { reader }
+    }
+
+    /// Returns the path and size of the next regular file, skipping the other members.
+    fn next_file(&mut self) -> Result<Option<(String, u64)>> {
+        let mut long_path = None;
+        loop {
+            let mut header = [0; TAR_BLOCK_SIZE as usize];
+            if self.reader.read(&mut header[..1])? == 0 {
+                // Some writers omit the end-of-archive blocks
+                return Ok(None);
+            }
+            self.reader.read_exact(&mut header[1..])?;
+            if header.iter().all(|&b| b == 0) {
+                return Ok(None);
+            }
+
+            let checksum = parse_octal(&header[148..156])?;
+            let actual = header
+                .iter()
+                .enumerate()
+                .map(|(i, &b)| {
+                    if (148..156).contains(&i) {
+                        32
+                    } else {
+                        u64::from(b)
+                    }
+                })
+                .sum::<u64>();
+            if checksum != actual {
+                return Err(Error::InvalidArchive {
+                    reason: "bad tar header checksum",
+                });
+            }
+
+            let size = parse_octal(&header[124..136])?;
+            let path = long_path.take();
+            match header[156] {
+                0 | b'0' | b'7' => {
+                    let path = match path {
+                        Some(path) => path,
+                        None => header_path(&header)?,
+                    };
+                    let path = path.strip_prefix("./").map(String::from).unwrap_or(path);
+                    return Ok(Some((path, size)));
+                }
+                b'L' => {
+                    let data = self.read_data(size)?;
+                    long_path = Some(String::from(nul_terminated(&data)?));
+                }
+                b'x' => {
+                    let data = self.read_data(size)?;
+                    long_path = pax_path(&data)?;
+                }
+                _ => self.skip_data(size)?,
+            }
+        }
+    }
+
+    fn read_data(&mut self, size: u64) -> Result<Vec<u8>> {
+        let len = usize::try_from(size).map_err(|_| Error::TooLarge {
+            what: "tar member",
+            size,
+        })?;
+        let data = aux::read_bytes(&mut self.reader, len)?;
+        self.skip(padding_size(size))?;
+        Ok(data)
+    }
+
+    fn skip_data(&mut self, size: u64) -> Result<()> {
+        self.skip(size)?;
+        self.skip(padding_size(size))
+    }
+
+    fn skip(&mut self, size: u64) -> Result<()> {
+        if (&mut self.reader).take(size).skip_to_end()? != size {
+            return Err(Error::InvalidArchive {
+                reason: "unexpected end of archive",
+            });
+        }
+        Ok(())
+    }
+}
+
+fn padding_size(size: u64) -> u64 {
+    (TAR_BLOCK_SIZE - size % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE
+}
+
+fn header_path(header: &[u8]) -> Result<String> {
+    let name = nul_terminated(&header[..100])?;
+    let prefix = nul_terminated(&header[345..500])?;
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        Ok(alloc::format!("{}/{}", prefix, name))
+    } else {
+        Ok(String::from(name))
+    }
+}
+
+fn nul_terminated(bytes: &[u8]) -> Result<&str> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    Ok(str::from_utf8(&bytes[..end])?)
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    if field[0] & 0x80 != 0 {
+        // The base-256 encoding of GNU tar for large numbers
+        return field[1..]
+            .iter()
+            .try_fold(u64::from(field[0] & 0x7F), |n, &b| {
+                n.checked_mul(256).map(|n| n + u64::from(b))
+            })
+            .ok_or(Error::InvalidArchive {
+                reason: "tar number out of range",
+            });
+    }
+    field
+        .iter()
+        .skip_while(|&&b| b == b' ')
+        .take_while(|&&b| b != 0 && b != b' ')
+        .try_fold(0u64, |n, &b| match b {
+            b'0'..=b'7' => n.checked_mul(8).map(|n| n + u64::from(b - b'0')),
+            _ => None,
+        })
+        .ok_or(Error::InvalidArchive {
+            reason: "bad tar number",
+        })
+}
+
+/// Returns the `path` record of a pax extended header.
+fn pax_path(mut data: &[u8]) -> Result<Option<String>> {
+    let error = || Error::InvalidArchive {
+        reason: "bad pax header",
+    };
+    let mut path = None;
+    while !data.is_empty() {
+        // Each record is "<length> <key>=<value>\n", where the length includes itself
+        let space = data.iter().position(|&b| b == b' ').ok_or_else(error)?;
+        let len = str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|&len| len > space && len <= data.len())
+            .ok_or_else(error)?;
+        let record = data[space + 1..len].strip_suffix(b"\n").ok_or_else(error)?;
+        if let Some(value) = record.strip_prefix(b"path=") {
+            path = Some(String::from(str::from_utf8(value)?));
+        }
+        data = &data[len..];
+    }
+    Ok(path)
+}
+
+fn with_entry_context<T, F>(entry: &ZipEntry, f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    f().map_err(|e| Error::InArchiveEntry {
+        name: entry.name.clone(),
+        source: Box::new(e),
+    })
+}
+
+fn find_end_of_central_directory(bytes: &[u8]) -> Result<usize> {
+    let last = bytes
+        .len()
+        .checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)
+        .ok_or(Error::InvalidArchive {
+            reason: "too short",
+        })?;
+    // The record is followed by a comment of at most 65535 bytes
+    let first = last.saturating_sub(usize::from(u16::MAX));
+    (first..=last)
+        .rev()
+        .find(|&i| bytes[i..][..4] == END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes())
+        .ok_or(Error::InvalidArchive {
+            reason: "end of central directory not found",
+        })
+}
+
+struct SliceReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+impl<'a> SliceReader<'a> {
+    fn new(bytes: &'a [u8], position: usize) -> Self {
+        SliceReader { bytes, position }
+    }
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .position
+            .checked_add(size)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or(Error::InvalidArchive {
+                reason: "unexpected end of archive",
+            })?;
+        self.position += size;
+        Ok(bytes)
+    }
+    fn skip(&mut self, size: usize) -> Result<()> {
+        self.bytes(size).map(|_| ())
+    }
+    fn u16(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        buf.copy_from_slice(self.bytes(2)?);
+        Ok(u16::from_le_bytes(buf))
+    }
+    fn u32(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+}
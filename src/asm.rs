@@ -0,0 +1,696 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::chunk::{
+    AtomChunk, AttrChunk, Chunk, CodeChunk, ExpTChunk, FunTChunk, ImpTChunk, LineChunk, LitTChunk,
+    LocTChunk, RawChunk, StandardChunk, StrTChunk,
+};
+use crate::code::{self, Instruction, Operand};
+use crate::parts::{self, AtomId, LineItem};
+use crate::term::{self, Term};
+use crate::{Error, Result, StandardBeamFile};
+
+/// The contents of an assembly file produced by `erlc -S`.
+///
+/// The file consists of the `module`, `exports`, `attributes` and `labels` forms
+/// followed by the functions, each of which is a `function` form and the instructions in it.
+///
+/// ```
+/// use beam_file::Assembly;
+/// use beam_file::chunk::{CodeChunk, ExpTChunk};
+///
+/// let text = "
+/// {module, foo}.
+/// {exports, [{bar,0}]}.
+/// {attributes, []}.
+/// {labels, 3}.
+///
+/// {function, bar, 0, 2}.
+///   {label,1}.
+///     {func_info,{atom,foo},{atom,bar},0}.
+///   {label,2}.
+///     {move,{literal,\"baz\"},{x,0}}.
+///     return.
+/// ";
+/// let assembly = text.parse::<Assembly>().unwrap();
+/// assert_eq!("bar", assembly.functions[0].name);
+///
+/// let beam = assembly.assemble().unwrap();
+/// assert_eq!(3, beam.chunk::<CodeChunk>().unwrap().label_count);
+/// assert_eq!(2, beam.chunk::<ExpTChunk>().unwrap().exports[0].label);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assembly {
+    pub module: String,
+    pub exports: Vec<(String, u32)>,
+
+    /// The list of the module attributes (e.g., `[{vsn,[1]}]`).
+    pub attributes: Term,
+
+    /// The number of labels (i.e., the largest label plus one).
+    pub labels: u32,
+
+    pub functions: Vec<AssemblyFunction>,
+}
+impl Assembly {
+    /// Parses the text of an assembly file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut forms = term::parse_forms(text)?.into_iter();
+        let mut header = |name: &'static str| {
+            let (line, form) = forms.next().ok_or(invalid(0, "missing header"))?;
+            match form {
+                Term::Tuple(mut elements)
+                    if elements.len() == 2 && elements[0] == Term::atom(name) =>
+                {
+                    Ok((line, elements.pop().expect("two elements")))
+                }
+                _ => Err(invalid(line, "unexpected header form")),
+            }
+        };
+        let module = match header("module")? {
+            (_, Term::Atom(name)) => name,
+            (line, _) => return Err(invalid(line, "module name is not an atom")),
+        };
+        let exports = match header("exports")? {
+            (line, Term::List(exports)) => exports
+                .into_iter()
+                .map(|export| match export {
+                    Term::Tuple(ref e) => match e[..] {
+                        [Term::Atom(ref name), Term::Integer(arity)] => {
+                            Ok((name.clone(), to_u32(line, arity)?))
+                        }
+                        _ => Err(invalid(line, "malformed export")),
+                    },
+                    _ => Err(invalid(line, "malformed export")),
+                })
+                .collect::<Result<_>>()?,
+            (line, _) => return Err(invalid(line, "exports are not a list")),
+        };
+        let attributes = header("attributes")?.1;
+        let labels = match header("labels")? {
+            (line, Term::Integer(n)) => to_u32(line, n)?,
+            (line, _) => return Err(invalid(line, "label count is not an integer")),
+        };
+
+        let mut functions = Vec::<AssemblyFunction>::new();
+        for (line, form) in forms {
+            if let Term::Tuple(ref elements) = form {
+                if let [Term::Atom(ref tag), Term::Atom(ref name), Term::Integer(arity), Term::Integer(entry)] =
+                    elements[..]
+                {
+                    if tag == "function" {
+                        functions.push(AssemblyFunction {
+                            name: name.clone(),
+                            arity: to_u32(line, arity)?,
+                            entry: to_u32(line, entry)?,
+                            instructions: Vec::new(),
+                        });
+                        continue;
+                    }
+                }
+            }
+            functions
+                .last_mut()
+                .ok_or(invalid(line, "instruction outside of a function"))?
+                .instructions
+                .push(form);
+        }
+        Ok(Assembly {
+            module,
+            exports,
+            attributes,
+            labels,
+            functions,
+        })
+    }
+
+    /// Assembles the module into a BEAM file in the same way as `beam_asm`.
+    ///
+    /// The file has the `"AtU8"`, `"Code"`, `"StrT"`, `"ImpT"`, `"ExpT"`, `"FunT"`, `"LitT"`,
+    /// `"LocT"`, `"Attr"` and `"Line"` chunks, where the ones with no entries among
+    /// `"FunT"`, `"LitT"` and `"Line"` are omitted.
+    pub fn assemble(&self) -> Result<StandardBeamFile> {
+        let mut asm = Assembler::new(self);
+        let mut instructions = Vec::new();
+        let mut code_size = 0;
+        for function in &self.functions {
+            asm.function(function);
+            for (index, term) in function.instructions.iter().enumerate() {
+                let offset = code_size;
+                let instruction = asm
+                    .instruction(term)
+                    .and_then(|instruction| {
+                        code_size += code::encode(instruction.as_slice())?.len() as u64;
+                        Ok(instruction)
+                    })
+                    .map_err(|e| Error::InFunction {
+                        name: function.name.clone(),
+                        arity: function.arity,
+                        source: Box::new(Error::InEntry {
+                            index,
+                            offset,
+                            source: Box::new(e),
+                        }),
+                    })?;
+                instructions.extend(instruction);
+            }
+        }
+        instructions.push(Instruction::new("int_code_end", Vec::new()));
+        asm.finish(self, &instructions)
+    }
+}
+impl core::str::FromStr for Assembly {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// A function in an assembly file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssemblyFunction {
+    pub name: String,
+    pub arity: u32,
+
+    /// The label of the first instruction after `func_info`.
+    pub entry: u32,
+
+    /// The instructions including labels (e.g., `{label,1}` and `{move,{x,0},{y,0}}`).
+    pub instructions: Vec<Term>,
+}
+
+/// The tables built while assembling, which correspond to `beam_dict`.
+struct Assembler<'a> {
+    assembly: &'a Assembly,
+    atoms: Vec<String>,
+    atom_ids: BTreeMap<String, AtomId>,
+    imports: Vec<parts::Import>,
+    import_ids: BTreeMap<(AtomId, AtomId, u32), u32>,
+    exports: Vec<parts::Export>,
+    locals: Vec<parts::Local>,
+    funs: Vec<parts::Function>,
+    literals: Vec<Vec<u8>>,
+    literal_ids: BTreeMap<Vec<u8>, u32>,
+    strings: Vec<u8>,
+    file_names: Vec<String>,
+    line_items: Vec<LineItem>,
+    line_ids: BTreeMap<(u32, u32), u32>,
+    line_file: u32,
+    line_count: u32,
+    opcode_max: code::Opcode,
+}
+impl<'a> Assembler<'a> {
+    fn new(assembly: &'a Assembly) -> Self {
+        let mut asm = Assembler {
+            assembly,
+            atoms: Vec::new(),
+            atom_ids: BTreeMap::new(),
+            imports: Vec::new(),
+            import_ids: BTreeMap::new(),
+            exports: Vec::new(),
+            locals: Vec::new(),
+            funs: Vec::new(),
+            literals: Vec::new(),
+            literal_ids: BTreeMap::new(),
+            strings: Vec::new(),
+            file_names: Vec::new(),
+            line_items: Vec::new(),
+            line_ids: BTreeMap::new(),
+            line_file: 0,
+            line_count: 0,
+            opcode_max: 0,
+        };
+        asm.atom(&assembly.module);
+        asm
+    }
+
+    fn function(&mut self, function: &AssemblyFunction) {
+        let atom = self.atom(&function.name);
+        let is_exported = self
+            .assembly
+            .exports
+            .iter()
+            .any(|(name, arity)| *name == function.name && *arity == function.arity);
+        if is_exported {
+            self.exports.push(parts::Export {
+                function: atom,
+                arity: function.arity,
+                label: function.entry,
+            });
+        } else {
+            self.locals.push(parts::Local {
+                function: atom,
+                arity: function.arity,
+                label: function.entry,
+            });
+        }
+    }
+
+    /// Converts `term` to an instruction, or returns `None` for a comment (i.e., `{'%',_}`).
+    fn instruction(&mut self, term: &Term) -> Result<Option<Instruction>> {
+        let (name, args) = match *term {
+            Term::Atom(ref name) => (name.as_str(), Vec::new()),
+            Term::Tuple(ref elements) => match elements[..] {
+                [Term::Atom(ref tag), ..] if tag == "%" => return Ok(None),
+                [Term::Atom(ref name), ref args @ ..] => self.special_instruction(name, args)?,
+                _ => return Err(unassemblable("malformed instruction")),
+            },
+            _ => return Err(unassemblable("malformed instruction")),
+        };
+        let opcode = code::opcode(name).ok_or(unassemblable("unknown instruction"))?;
+        if code::opcode_arity(opcode) != Some(args.len()) {
+            return Err(unassemblable("wrong number of operands"));
+        }
+        self.opcode_max = self.opcode_max.max(opcode);
+        Ok(Some(Instruction { opcode, args }))
+    }
+
+    /// Converts the instructions whose textual form differs from the operands in the bytecode.
+    fn special_instruction<'t>(
+        &mut self,
+        name: &'t str,
+        args: &'t [Term],
+    ) -> Result<(&'t str, Vec<Operand>)> {
+        let instruction = match (name, args) {
+            ("line", [Term::List(locations)]) => ("line", vec![self.line(locations)?]),
+            ("bif", [Term::Atom(bif), fail, Term::List(bif_args), dst]) => {
+                let import = self.bif(bif, bif_args.len());
+                let name = match bif_args.len() {
+                    0 => return Ok(("bif0", vec![import, self.operand(dst)?])),
+                    1 => "bif1",
+                    2 => "bif2",
+                    _ => return Err(unassemblable("too many bif arguments")),
+                };
+                let mut operands = vec![self.operand(fail)?, import];
+                operands.extend(self.operands(bif_args)?);
+                operands.push(self.operand(dst)?);
+                (name, operands)
+            }
+            ("gc_bif", [Term::Atom(bif), fail, live, Term::List(bif_args), dst]) => {
+                let name = match bif_args.len() {
+                    1 => "gc_bif1",
+                    2 => "gc_bif2",
+                    3 => "gc_bif3",
+                    _ => return Err(unassemblable("wrong number of gc_bif arguments")),
+                };
+                let mut operands = vec![
+                    self.operand(fail)?,
+                    self.operand(live)?,
+                    self.bif(bif, bif_args.len()),
+                ];
+                operands.extend(self.operands(bif_args)?);
+                operands.push(self.operand(dst)?);
+                (name, operands)
+            }
+            ("test", [Term::Atom(test), fail, Term::List(test_args)]) => {
+                let mut operands = vec![self.operand(fail)?];
+                operands.extend(self.operands(test_args)?);
+                (test.as_str(), operands)
+            }
+            ("test", [Term::Atom(test), fail, live, Term::List(test_args), dst]) => {
+                let mut operands = vec![self.operand(fail)?, self.operand(live)?];
+                operands.extend(self.operands(test_args)?);
+                operands.push(self.operand(dst)?);
+                (test.as_str(), operands)
+            }
+            ("test", [Term::Atom(test), rest @ ..]) => (test.as_str(), self.operands(rest)?),
+            ("make_fun2", [label, index, old_uniq, num_free]) => {
+                let fun = self.fun(label, index, old_uniq, num_free)?;
+                (name, vec![fun])
+            }
+            ("make_fun3", [label, index, old_uniq, dst, free]) => {
+                let num_free = match free {
+                    Term::Tuple(e) => match &e[..] {
+                        [_, Term::List(free)] => Term::Integer(free.len() as i64),
+                        _ => return Err(unassemblable("malformed free variables")),
+                    },
+                    _ => return Err(unassemblable("malformed free variables")),
+                };
+                let fun = self.fun(label, index, old_uniq, &num_free)?;
+                (name, vec![fun, self.operand(dst)?, self.operand(free)?])
+            }
+            _ => (name, self.operands(args)?),
+        };
+        Ok(instruction)
+    }
+
+    fn operands(&mut self, terms: &[Term]) -> Result<Vec<Operand>> {
+        terms.iter().map(|t| self.operand(t)).collect()
+    }
+
+    fn operand(&mut self, term: &Term) -> Result<Operand> {
+        let elements = match *term {
+            Term::Integer(n) => {
+                return u64::try_from(n)
+                    .map(Operand::Unsigned)
+                    .map_err(|_| unassemblable("negative untagged integer"))
+            }
+            Term::Atom(ref name) if name == "nil" => return Ok(Operand::Atom(0)),
+            Term::Tuple(ref elements) => elements,
+            _ => return Err(unassemblable("unknown operand")),
+        };
+        let operand = match elements[..] {
+            [Term::Atom(ref tag), ref value] => match (tag.as_str(), value) {
+                ("x", &Term::Integer(n)) => Operand::X(operand_u32(n)?),
+                ("y", &Term::Integer(n)) => Operand::Y(operand_u32(n)?),
+                ("f", &Term::Integer(n)) => Operand::Label(operand_u32(n)?),
+                ("fr", &Term::Integer(n)) => Operand::FloatRegister(operand_u32(n)?),
+                ("char", &Term::Integer(n)) => Operand::Character(operand_u32(n)?),
+                ("atom", Term::Atom(name)) => Operand::Atom(self.atom(name)),
+                ("atom", Term::List(l)) if l.is_empty() => Operand::Atom(0),
+                ("integer", &Term::Integer(n)) => Operand::Integer(n),
+                (
+                    "integer",
+                    &Term::BigInteger {
+                        negative,
+                        ref magnitude,
+                    },
+                ) => Operand::BigInteger {
+                    negative,
+                    magnitude: magnitude.clone(),
+                },
+                ("literal" | "float", literal) => Operand::Literal(self.literal(literal)?),
+                ("list", Term::List(elements)) => Operand::List(self.operands(elements)?),
+                ("alloc", Term::List(pairs)) => Operand::AllocList(
+                    pairs
+                        .iter()
+                        .map(|pair| match pair {
+                            Term::Tuple(e) => match &e[..] {
+                                [Term::Atom(kind), Term::Integer(n)] => {
+                                    let kind = match kind.as_str() {
+                                        "words" => 0,
+                                        "floats" => 1,
+                                        "funs" => 2,
+                                        _ => return Err(unassemblable("unknown allocation kind")),
+                                    };
+                                    Ok((kind, operand_u32(*n)?))
+                                }
+                                _ => Err(unassemblable("malformed allocation")),
+                            },
+                            _ => Err(unassemblable("malformed allocation")),
+                        })
+                        .collect::<Result<_>>()?,
+                ),
+                ("field_flags", flags) => Operand::Unsigned(field_flags(flags)?),
+                ("string", string) => {
+                    let bytes = string_bytes(string)?;
+                    Operand::Unsigned(u64::from(self.string(&bytes)))
+                }
+                _ => return Err(unassemblable("unknown operand")),
+            },
+            [Term::Atom(ref tag), ref register, Term::Integer(index)] if tag == "tr" => {
+                Operand::TypedRegister(Box::new(self.operand(register)?), operand_u32(index)?)
+            }
+            [Term::Atom(ref tag), Term::Atom(ref module), Term::Atom(ref function), Term::Integer(arity)]
+                if tag == "extfunc" =>
+            {
+                let arity = operand_u32(arity)?;
+                Operand::Unsigned(u64::from(self.import(module, function, arity)))
+            }
+            _ => return Err(unassemblable("unknown operand")),
+        };
+        Ok(operand)
+    }
+
+    fn atom(&mut self, name: &str) -> AtomId {
+        if let Some(&id) = self.atom_ids.get(name) {
+            return id;
+        }
+        self.atoms.push(String::from(name));
+        let id = self.atoms.len() as AtomId;
+        self.atom_ids.insert(String::from(name), id);
+        id
+    }
+
+    fn import(&mut self, module: &str, function: &str, arity: u32) -> u32 {
+        let key = (self.atom(module), self.atom(function), arity);
+        if let Some(&index) = self.import_ids.get(&key) {
+            return index;
+        }
+        let index = self.imports.len() as u32;
+        self.imports.push(parts::Import {
+            module: key.0,
+            function: key.1,
+            arity,
+        });
+        self.import_ids.insert(key, index);
+        index
+    }
+
+    fn bif(&mut self, name: &str, arity: usize) -> Operand {
+        Operand::Unsigned(u64::from(self.import("erlang", name, arity as u32)))
+    }
+
+    fn literal(&mut self, literal: &Term) -> Result<u32> {
+        let bytes = literal.encode()?;
+        if let Some(&index) = self.literal_ids.get(&bytes) {
+            return Ok(index);
+        }
+        let index = self.literals.len() as u32;
+        self.literals.push(bytes.clone());
+        self.literal_ids.insert(bytes, index);
+        Ok(index)
+    }
+
+    /// Returns the offset of `bytes` in the string table, reusing an existing occurrence.
+    fn string(&mut self, bytes: &[u8]) -> u32 {
+        if bytes.is_empty() {
+            return 0;
+        }
+        let offset = match self.strings.windows(bytes.len()).position(|w| w == bytes) {
+            Some(offset) => offset,
+            None => {
+                self.strings.extend_from_slice(bytes);
+                self.strings.len() - bytes.len()
+            }
+        };
+        offset as u32
+    }
+
+    fn fun(
+        &mut self,
+        label: &Term,
+        index: &Term,
+        old_uniq: &Term,
+        num_free: &Term,
+    ) -> Result<Operand> {
+        let label = match self.operand(label)? {
+            Operand::Label(label) => label,
+            _ => return Err(unassemblable("fun label is not {f,Label}")),
+        };
+        let arity = self
+            .assembly
+            .functions
+            .iter()
+            .find(|f| f.entry == label)
+            .ok_or(unassemblable("fun label is not the entry of a function"))?
+            .arity;
+        let number = |term: &Term| match *term {
+            Term::Integer(n) => operand_u32(n),
+            _ => Err(unassemblable("malformed fun")),
+        };
+        // The name is resolved in `finish` since `beam_asm` adds it to the atom table later
+        self.funs.push(parts::Function {
+            function: 0,
+            arity,
+            label,
+            index: number(index)?,
+            num_free: number(num_free)?,
+            old_uniq: number(old_uniq)?,
+        });
+        Ok(Operand::Unsigned(self.funs.len() as u64 - 1))
+    }
+
+    /// Returns the operand of `line` for `[{location,File,Line}]` (or `[]`).
+    fn line(&mut self, locations: &[Term]) -> Result<Operand> {
+        self.line_count += 1;
+        let (file, line) = match locations {
+            [] => return Ok(Operand::Unsigned(0)),
+            [Term::Tuple(e)] => match &e[..] {
+                [Term::Atom(tag), file, Term::Integer(line)] if tag == "location" => {
+                    (string_bytes(file)?, operand_u32(*line)?)
+                }
+                _ => return Err(unassemblable("malformed location")),
+            },
+            _ => return Err(unassemblable("malformed location")),
+        };
+        let file = String::from_utf8(file).map_err(|e| e.utf8_error())?;
+
+        // The file index `0` refers to the source of the module itself
+        let file = if file == format!("{}.erl", self.assembly.module) {
+            0
+        } else if let Some(i) = self.file_names.iter().position(|f| *f == file) {
+            i as u32 + 1
+        } else {
+            self.file_names.push(file);
+            self.file_names.len() as u32
+        };
+        if let Some(&index) = self.line_ids.get(&(file, line)) {
+            return Ok(Operand::Unsigned(u64::from(index)));
+        }
+        if file != self.line_file {
+            self.line_items.push(LineItem::File(file));
+            self.line_file = file;
+        }
+        self.line_items.push(LineItem::Line(line));
+        let index = self.line_ids.len() as u32 + 1;
+        self.line_ids.insert((file, line), index);
+        Ok(Operand::Unsigned(u64::from(index)))
+    }
+
+    fn finish(
+        mut self,
+        assembly: &Assembly,
+        instructions: &[Instruction],
+    ) -> Result<StandardBeamFile> {
+        for (name, arity) in &assembly.exports {
+            if !assembly
+                .functions
+                .iter()
+                .any(|f| f.name == *name && f.arity == *arity)
+            {
+                return Err(unassemblable("exported function is not defined"));
+            }
+        }
+        for fun in &mut self.funs {
+            let function = assembly
+                .functions
+                .iter()
+                .find(|f| f.entry == fun.label)
+                .expect("fun label");
+            fun.function = self.atom_ids[&function.name];
+        }
+        // `beam_dict` prepends the entries
+        self.exports.reverse();
+        self.locals.reverse();
+
+        let code = CodeChunk {
+            info_size: 16,
+            version: 0,
+            opcode_max: u32::from(
+                self.opcode_max
+                    .max(code::opcode("int_code_end").expect("int_code_end")),
+            ),
+            label_count: assembly.labels,
+            function_count: assembly.functions.len() as u32,
+            bytecode: code::encode(instructions)?,
+        };
+        let mut chunks = vec![
+            StandardChunk::Atom(AtomChunk {
+                is_unicode: true,
+                atoms: self
+                    .atoms
+                    .into_iter()
+                    .map(|name| parts::Atom { name })
+                    .collect(),
+            }),
+            StandardChunk::Code(code),
+            StandardChunk::StrT(StrTChunk {
+                strings: self.strings,
+            }),
+            StandardChunk::ImpT(ImpTChunk {
+                imports: self.imports,
+            }),
+            StandardChunk::ExpT(ExpTChunk {
+                exports: self.exports,
+            }),
+        ];
+        if !self.funs.is_empty() {
+            chunks.push(StandardChunk::FunT(FunTChunk {
+                functions: self.funs,
+            }));
+        }
+        if !self.literals.is_empty() {
+            chunks.push(StandardChunk::LitT(LitTChunk {
+                literals: self.literals,
+            }));
+        }
+        chunks.push(StandardChunk::LocT(LocTChunk {
+            locals: self.locals,
+        }));
+        chunks.push(StandardChunk::Attr(AttrChunk {
+            term: assembly.attributes.encode()?,
+        }));
+        if self.line_count > 0 {
+            let line = LineChunk {
+                version: 0,
+                flags: 0,
+                instruction_count: self.line_count,
+                items: self.line_items,
+                file_names: self.file_names,
+            };
+            let mut data = Vec::new();
+            line.encode_data(&mut data)?;
+            chunks.push(StandardChunk::Unknown(RawChunk {
+                id: *line.id(),
+                data,
+            }));
+        }
+        Ok(StandardBeamFile { chunks })
+    }
+}
+
+/// Returns the bits of `{field_flags,Flags}` in the same way as `beam_asm`.
+fn field_flags(flags: &Term) -> Result<u64> {
+    let flags = match *flags {
+        Term::Integer(n) => return u64::try_from(n).map_err(|_| unassemblable("negative flags")),
+        Term::List(ref flags) => flags,
+        _ => return Err(unassemblable("malformed field flags")),
+    };
+    let mut bits = 0;
+    for flag in flags {
+        bits |= match *flag {
+            Term::Atom(ref flag) => match flag.as_str() {
+                "little" => 0x02,
+                "signed" => 0x04,
+                "native" => 0x10,
+                "big" | "unsigned" => 0,
+                _ => return Err(unassemblable("unknown field flag")),
+            },
+            // e.g., `{anno,[...]}`
+            Term::Tuple(_) => 0,
+            _ => return Err(unassemblable("unknown field flag")),
+        };
+    }
+    Ok(bits)
+}
+
+/// Returns the bytes of a string written as a list or a binary.
+fn string_bytes(term: &Term) -> Result<Vec<u8>> {
+    match *term {
+        Term::Binary(ref bytes) => Ok(bytes.clone()),
+        Term::List(ref chars) => {
+            let mut s = String::new();
+            for c in chars {
+                let c = match *c {
+                    Term::Integer(n) => u32::try_from(n).ok().and_then(char::from_u32),
+                    _ => None,
+                };
+                s.push(c.ok_or(unassemblable("malformed string"))?);
+            }
+            Ok(s.into_bytes())
+        }
+        _ => Err(unassemblable("malformed string")),
+    }
+}
+
+fn to_u32(line: usize, n: i64) -> Result<u32> {
+    u32::try_from(n).map_err(|_| invalid(line, "integer out of range"))
+}
+
+fn operand_u32(n: i64) -> Result<u32> {
+    u32::try_from(n).map_err(|_| unassemblable("integer out of range"))
+}
+
+fn unassemblable(reason: &'static str) -> Error {
+    Error::InvalidAssemblyTerm { reason }
+}
+
+fn invalid(line: usize, reason: &'static str) -> Error {
+    Error::InvalidAssembly { line, reason }
+}
@@ -0,0 +1,143 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::beam_file::{self, Header, CHUNK_HEADER_SIZE, HEADER_SIZE};
+use crate::chunk::{aux, Chunk, Id};
+use crate::{BeamFile, DecodeLimits, Error, Limit, Result};
+
+impl<C: Chunk> BeamFile<C> {
+    /// Reads a BEAM file from the asynchronous `reader`.
+    ///
+    /// The payload is read asynchronously and then decoded in the same way as `from_reader`.
+    ///
+    /// ```
+    /// use beam_file::StandardBeamFile;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let f = tokio::fs::File::open("tests/testdata/test.beam").await.unwrap();
+    /// let beam = StandardBeamFile::from_async_reader(f).await.unwrap();
+    /// assert_eq!(12, beam.chunks.len());
+    /// # });
+    /// ```
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Self> {
+        Self::from_async_reader_with_limits(reader, &DecodeLimits::default()).await
+    }
+
+    /// Reads a BEAM file from the asynchronous `reader` within `limits`.
+    pub async fn from_async_reader_with_limits<R: AsyncRead + Unpin>(
+        mut reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self> {
+        let header = read_header(&mut reader).await?;
+        let payload_size = header.chunks_size(limits)?;
+        let mut payload = Vec::new();
+        (&mut reader)
+            .take(payload_size)
+            .read_to_end(&mut payload)
+            .await?;
+        beam_file::check_payload_size(payload_size, payload.len())?;
+        let chunks = beam_file::decode_payload(&payload, limits)?;
+        Ok(BeamFile { chunks })
+    }
+
+    /// Writes the BEAM file to the asynchronous `writer`.
+    pub async fn to_async_writer<W: AsyncWrite + Unpin>(&self, mut writer: W) -> Result<()> {
+        let mut buf = Vec::new();
+        self.to_writer(&mut buf)?;
+        writer.write_all(&buf).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+/// A reader which decodes the chunks of a BEAM file one at a time from an asynchronous reader.
+///
+/// ```
+/// use beam_file::AsyncChunkReader;
+/// use beam_file::chunk::{Chunk, RawChunk};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let f = tokio::fs::File::open("tests/testdata/test.beam").await.unwrap();
+/// let mut reader = AsyncChunkReader::new(f).await.unwrap();
+/// let chunk: RawChunk = reader.next_chunk().await.unwrap().unwrap();
+/// assert_eq!(b"Atom", chunk.id());
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct AsyncChunkReader<R> {
+    reader: R,
+    limits: DecodeLimits,
+    position: u64,
+    payload_size: u64,
+}
+impl<R: AsyncRead + Unpin> AsyncChunkReader<R> {
+    /// Makes a new `AsyncChunkReader` after reading the `"FOR1"` header from `reader`.
+    pub async fn new(reader: R) -> Result<Self> {
+        Self::with_limits(reader, DecodeLimits::default()).await
+    }
+
+    /// Makes a new `AsyncChunkReader` which decodes chunks within `limits`.
+    pub async fn with_limits(mut reader: R, limits: DecodeLimits) -> Result<Self> {
+        let header = read_header(&mut reader).await?;
+        let payload_size = header.chunks_size(&limits)?;
+        Ok(AsyncChunkReader {
+            reader,
+            limits,
+            position: 0,
+            payload_size,
+        })
+    }
+
+    /// Reads the next chunk.
+    ///
+    /// Returns `Ok(None)` at the end of the payload.
+    pub async fn next_chunk<C: Chunk>(&mut self) -> Result<Option<C>> {
+        let rest = self.payload_size - self.position;
+        let offset = HEADER_SIZE + self.position;
+        if rest == 0 {
+            return Ok(None);
+        }
+        if rest < CHUNK_HEADER_SIZE {
+            return Err(Error::TrailingGarbage { offset, size: rest });
+        }
+
+        let mut id: Id = [0; 4];
+        let chunk = self
+            .read_chunk(&mut id, rest)
+            .await
+            .map_err(|e| Error::in_chunk(id, offset, e))?;
+        Ok(Some(chunk))
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    async fn read_chunk<C: Chunk>(&mut self, id: &mut Id, rest: u64) -> Result<C> {
+        let mut buf = [0; CHUNK_HEADER_SIZE as usize];
+        self.reader.read_exact(&mut buf).await?;
+        let header = aux::Header::decode(&buf[..])?;
+        *id = header.chunk_id;
+        self.limits
+            .check(Limit::ChunkSize, u64::from(header.data_size))?;
+        let size = beam_file::padded_data_size(header.data_size, rest - CHUNK_HEADER_SIZE)?;
+
+        let mut buf = Vec::new();
+        (&mut self.reader).take(size).read_to_end(&mut buf).await?;
+        if (buf.len() as u64) < size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        self.position += CHUNK_HEADER_SIZE + size;
+
+        buf.truncate(header.data_size as usize);
+        C::decode_data_with_limits(id, &buf[..], &self.limits)
+    }
+}
+
+async fn read_header<R: AsyncRead + Unpin>(mut reader: R) -> Result<Header> {
+    let mut buf = [0; HEADER_SIZE as usize];
+    reader.read_exact(&mut buf).await?;
+    let header = Header::from_reader(&buf[..])?;
+    header.check()?;
+    Ok(header)
+}
@@ -1,10 +1,18 @@
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::io::{Cursor, Read, Write};
+#[cfg(feature = "std")]
+use std::io::{BufReader, BufWriter};
+#[cfg(feature = "std")]
 use std::path::Path;
 
-use crate::chunk::Chunk;
-use crate::{Error, Result};
+use crate::io::{Cursor, Read, Seek, SeekFrom, Write};
+use crate::io_ext::{ReadExt, WriteExt};
+
+use crate::chunk::{aux, AsChunk, Chunk, ExtendedChunk, Id, LenientChunk, RawChunk, TypedChunk};
+use crate::{DecodeLimits, Error, Limit, Result};
 
 /// A BEAM File
 ///
@@ -16,79 +24,449 @@ use crate::{Error, Result};
 /// assert_eq!(b"Atom", beam.chunks.iter().nth(0).map(|c| c.id()).unwrap());
 /// ```
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeamFile<C> {
     pub chunks: Vec<C>,
 }
 impl<C: Chunk> BeamFile<C> {
+    /// Reads a BEAM file from `path` (see `from_file_with_limits` for the error reporting).
+    #[cfg(feature = "std")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let f = File::open(path)?;
-        Self::from_reader(f)
+        Self::from_file_with_limits(path, &DecodeLimits::default())
+    }
+
+    /// Reads a BEAM file from `path` within `limits`.
+    ///
+    /// Unlike `from_reader_with_limits`, this also reports the bytes following the `"FOR1"` payload
+    /// as `Error::TrailingGarbage`. A file shorter than the payload size is reported as
+    /// `Error::TruncatedPayload`, just like `from_reader_with_limits` does.
+    ///
+    /// Errors are wrapped in `Error::InFile` to tell which file failed;
+    /// use `Error::root_cause` to get the underlying error.
+    ///
+    /// ```
+    /// use beam_file::{Error, RawBeamFile};
+    ///
+    /// let e = RawBeamFile::from_file("tests/testdata/no_such_file.beam").unwrap_err();
+    /// assert!(matches!(e, Error::InFile { .. }));
+    /// assert!(matches!(e.root_cause(), Error::Io(_)));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_file_with_limits<P: AsRef<Path>>(path: P, limits: &DecodeLimits) -> Result<Self> {
+        let path = path.as_ref();
+        let decode = || {
+            let f = File::open(path)?;
+            let file_size = f.metadata()?.len();
+            Self::read_from(BufReader::new(f), limits, Some(file_size))
+        };
+        decode().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_reader_with_limits(reader, &DecodeLimits::default())
     }
-    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
-        let expected = Header::new(0);
-        let header = Header::from_reader(&mut reader)?;
-        if header.magic_number != expected.magic_number {
-            return Err(Error::UnexpectedMagicNumber {
-                magic_number: header.magic_number,
-            });
-        }
-        if header.type_id != expected.type_id {
-            return Err(Error::UnexpectedFormType {
-                form_type: header.type_id,
-            });
-        }
 
-        let mut buf = vec![0; (header.payload_size - 4) as usize];
-        reader.read_exact(&mut buf)?;
+    /// Reads a BEAM file from `reader` within `limits`.
+    pub fn from_reader_with_limits<R: Read>(reader: R, limits: &DecodeLimits) -> Result<Self> {
+        Self::read_from(reader, limits, None)
+    }
 
-        let mut chunks = Vec::new();
-        let mut cursor = Cursor::new(&buf);
-        while cursor.position() < buf.len() as u64 {
-            chunks.push(C::decode(&mut cursor)?);
+    fn read_from<R: Read>(
+        mut reader: R,
+        limits: &DecodeLimits,
+        file_size: Option<u64>,
+    ) -> Result<Self> {
+        let header = Header::from_reader(&mut reader)?;
+        header.check()?;
+        // Bytes past the `"FOR1"` payload are reported in the same way as those past the last chunk
+        let end = u64::from(header.payload_size) + 8;
+        if let Some(file_size) = file_size.filter(|&size| size > end) {
+            return Err(Error::TrailingGarbage {
+                offset: end,
+                size: file_size - end,
+            });
         }
+        let payload = read_payload(reader, &header, limits)?;
+        let chunks = decode_payload(&payload, limits)?;
         Ok(BeamFile { chunks })
     }
 
+    #[cfg(feature = "std")]
     pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let f = File::create(path)?;
-        self.to_writer(f)
+        let mut writer = BufWriter::new(f);
+        self.to_seekable_writer(&mut writer)?;
+        writer.flush()?;
+        Ok(())
     }
-    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<()> {
         let mut buf = Vec::new();
         for chunk in &self.chunks {
             chunk.encode(&mut buf)?;
         }
 
-        let header = Header::new(buf.len() as u32 + 4);
-        header.to_writer(&mut writer)?;
-        writer.write_all(&buf)?;
-        Ok(())
+        write_payload(writer, &buf)
+    }
+
+    /// Writes the BEAM file to `writer` without buffering the encoded chunks.
+    ///
+    /// The sizes in the `"FOR1"` and chunk headers are written by seeking back.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use beam_file::StandardBeamFile;
+    ///
+    /// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+    /// let mut buf = Cursor::new(Vec::new());
+    /// beam.to_seekable_writer(&mut buf).unwrap();
+    ///
+    /// let mut expected = Vec::new();
+    /// beam.to_writer(&mut expected).unwrap();
+    /// assert_eq!(expected, buf.into_inner());
+    /// ```
+    pub fn to_seekable_writer<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        write_seekable(writer, &self.chunks, &[])
+    }
+
+    /// Returns the first chunk of type `T`.
+    ///
+    /// ```
+    /// use beam_file::StandardBeamFile;
+    /// use beam_file::chunk::AtomChunk;
+    ///
+    /// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+    /// let atoms = beam.chunk::<AtomChunk>().unwrap();
+    /// assert_eq!("test", atoms.atoms[0].name);
+    /// ```
+    pub fn chunk<T: TypedChunk>(&self) -> Option<&T>
+    where
+        C: AsChunk<T>,
+    {
+        self.chunks.iter().find_map(|c| c.as_chunk())
+    }
+
+    /// Returns a mutable reference to the first chunk of type `T`.
+    pub fn chunk_mut<T: TypedChunk>(&mut self) -> Option<&mut T>
+    where
+        C: AsChunk<T>,
+    {
+        self.chunks.iter_mut().find_map(|c| c.as_chunk_mut())
+    }
+
+    /// Returns the first chunk which has the identifier `id`.
+    pub fn chunk_by_id(&self, id: &Id) -> Option<&C> {
+        self.chunks.iter().find(|c| c.id() == id)
+    }
+
+    /// Returns a mutable reference to the first chunk which has the identifier `id`.
+    pub fn chunk_by_id_mut(&mut self, id: &Id) -> Option<&mut C> {
+        self.chunks.iter_mut().find(|c| c.id() == id)
+    }
+
+    /// Replaces the first chunk stored under one of `T::IDS` with `chunk`,
+    /// or appends `chunk` if there is no such chunk.
+    ///
+    /// Returns the replaced chunk, if any.
+    ///
+    /// ```
+    /// use beam_file::StandardBeamFile;
+    /// use beam_file::chunk::{Chunk, StrTChunk};
+    ///
+    /// let mut beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+    /// let old = beam.insert_or_replace(StrTChunk { strings: b"foo".to_vec() });
+    /// assert_eq!(b"StrT", old.unwrap().id());
+    /// assert_eq!(b"foo", &beam.chunk::<StrTChunk>().unwrap().strings[..]);
+    /// ```
+    pub fn insert_or_replace<T: TypedChunk>(&mut self, chunk: T) -> Option<C>
+    where
+        C: AsChunk<T>,
+    {
+        let chunk = C::from_chunk(chunk);
+        match self.chunks.iter().position(|c| T::IDS.contains(c.id())) {
+            Some(i) => Some(core::mem::replace(&mut self.chunks[i], chunk)),
+            None => {
+                self.chunks.push(chunk);
+                None
+            }
+        }
+    }
+
+    /// Removes the first chunk which has the identifier `id`.
+    pub fn remove(&mut self, id: &Id) -> Option<C> {
+        let i = self.chunks.iter().position(|c| c.id() == id)?;
+        Some(self.chunks.remove(i))
+    }
+
+    /// Decodes the first chunk stored under one of `T::IDS` regardless of the chunk type `C`.
+    pub(crate) fn decode_chunk<T: TypedChunk>(&self) -> Result<Option<T>> {
+        let Some(chunk) = self.chunks.iter().find(|c| T::IDS.contains(c.id())) else {
+            return Ok(None);
+        };
+        let mut buf = Vec::new();
+        chunk.encode_data(&mut buf)?;
+        T::decode_data(chunk.id(), &buf[..]).map(Some)
+    }
+
+    /// Same as `decode_chunk` except that the absence of the chunk is an error.
+    pub(crate) fn require_chunk<T: TypedChunk>(&self) -> Result<T> {
+        self.decode_chunk()?
+            .ok_or(Error::MissingChunk { id: T::IDS[0] })
+    }
+}
+
+impl BeamFile<RawChunk> {
+    /// Decodes every chunk as `C`.
+    ///
+    /// Fails if any of the chunks cannot be decoded.
+    pub fn decode<C: Chunk>(&self) -> Result<BeamFile<C>> {
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|c| c.decode_as())
+            .collect::<Result<_>>()?;
+        Ok(BeamFile { chunks })
+    }
+
+    /// Decodes every chunk as `C`, keeping the chunks which cannot be decoded raw.
+    ///
+    /// ```
+    /// use beam_file::RawBeamFile;
+    /// use beam_file::chunk::{LenientChunk, StandardChunk};
+    ///
+    /// let raw = RawBeamFile::from_file("tests/testdata/test.beam").unwrap();
+    /// let beam = raw.decode_lenient::<StandardChunk>();
+    /// assert!(beam.chunks.iter().all(|c| c.error().is_none()));
+    /// ```
+    pub fn decode_lenient<C: Chunk>(self) -> BeamFile<LenientChunk<C>> {
+        let chunks = self
+            .chunks
+            .into_iter()
+            .map(LenientChunk::from_raw)
+            .collect();
+        BeamFile { chunks }
     }
 }
 
-struct Header {
-    magic_number: [u8; 4],
-    payload_size: u32,
-    type_id: [u8; 4],
+impl<C: Chunk, U: TypedChunk> BeamFile<ExtendedChunk<C, U>> {
+    /// Returns the first user-defined chunk.
+    ///
+    /// The typed chunks of the base chunk set are accessible with `chunk` as usual.
+    ///
+    /// ```
+    /// use beam_file::BeamFile;
+    /// use beam_file::chunk::{AtomChunk, ExtendedChunk, LineChunk, StandardChunk};
+    ///
+    /// let mut beam =
+    ///     BeamFile::<ExtendedChunk<StandardChunk, LineChunk>>::from_file("tests/testdata/test.beam")
+    ///         .unwrap();
+    /// assert_eq!("test", beam.chunk::<AtomChunk>().unwrap().atoms[0].name);
+    /// assert_eq!(0, beam.user_chunk().unwrap().version);
+    ///
+    /// beam.user_chunk_mut().unwrap().instruction_count = 0;
+    /// let line = LineChunk {
+    ///     version: 0,
+    ///     flags: 0,
+    ///     instruction_count: 0,
+    ///     items: Vec::new(),
+    ///     file_names: Vec::new(),
+    /// };
+    /// let old = beam.insert_or_replace_user(line).unwrap();
+    /// assert_eq!(0, old.as_user().unwrap().instruction_count);
+    /// assert!(beam.user_chunk().unwrap().items.is_empty());
+    /// ```
+    pub fn user_chunk(&self) -> Option<&U> {
+        self.chunks.iter().find_map(|c| c.as_user())
+    }
+
+    /// Returns a mutable reference to the first user-defined chunk.
+    pub fn user_chunk_mut(&mut self) -> Option<&mut U> {
+        self.chunks.iter_mut().find_map(|c| c.as_user_mut())
+    }
+
+    /// Replaces the first chunk stored under one of `U::IDS` with `chunk`,
+    /// or appends `chunk` if there is no such chunk.
+    ///
+    /// Returns the replaced chunk, if any.
+    pub fn insert_or_replace_user(&mut self, chunk: U) -> Option<ExtendedChunk<C, U>> {
+        let chunk = ExtendedChunk::User(chunk);
+        match self.chunks.iter().position(|c| U::IDS.contains(c.id())) {
+            Some(i) => Some(core::mem::replace(&mut self.chunks[i], chunk)),
+            None => {
+                self.chunks.push(chunk);
+                None
+            }
+        }
+    }
+}
+
+/// Reads the chunks part of the `"FOR1"` payload (i.e., the payload without the form type).
+pub(crate) fn read_payload<R: Read>(
+    reader: R,
+    header: &Header,
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>> {
+    let payload_size = header.chunks_size(limits)?;
+    let mut buf = Vec::new();
+    reader.take(payload_size).read_to_end(&mut buf)?;
+    check_payload_size(payload_size, buf.len())?;
+    Ok(buf)
+}
+
+/// Fails with `Error::TruncatedPayload` if fewer than `expected` bytes of the payload were read.
+pub(crate) fn check_payload_size(expected: u64, actual: usize) -> Result<()> {
+    if actual as u64 != expected {
+        return Err(Error::TruncatedPayload {
+            expected,
+            actual: actual as u64,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the size of the chunk data with `data_size` bytes followed by its padding,
+/// failing if it exceeds the `rest` bytes of the payload.
+pub(crate) fn padded_data_size(data_size: u32, rest: u64) -> Result<u64> {
+    let size = u64::from(data_size) + u64::from(aux::padding_size(data_size));
+    if size > rest {
+        return Err(Error::TruncatedPayload {
+            expected: size,
+            actual: rest,
+        });
+    }
+    Ok(size)
+}
+
+/// Decodes the chunks of `payload`, failing if it has bytes following the last chunk.
+pub(crate) fn decode_payload<C: Chunk>(payload: &[u8], limits: &DecodeLimits) -> Result<Vec<C>> {
+    let (chunks, end) = decode_chunks(payload, limits)?;
+    if end < payload.len() {
+        return Err(Error::TrailingGarbage {
+            offset: HEADER_SIZE + end as u64,
+            size: (payload.len() - end) as u64,
+        });
+    }
+    Ok(chunks)
+}
+
+/// Decodes chunks from `payload` until the rest is too short to contain a chunk header.
+///
+/// Returns the decoded chunks and the end position of the last one.
+pub(crate) fn decode_chunks<C: Chunk>(
+    payload: &[u8],
+    limits: &DecodeLimits,
+) -> Result<(Vec<C>, usize)> {
+    let mut chunks = Vec::new();
+    let mut cursor = Cursor::new(payload);
+    while payload.len() as u64 - cursor.position() >= CHUNK_HEADER_SIZE {
+        let position = cursor.position() as usize;
+        let mut id = [0; 4];
+        id.copy_from_slice(&payload[position..][..4]);
+        let chunk = C::decode_with_limits(&mut cursor, limits)
+            .map_err(|e| Error::in_chunk(id, HEADER_SIZE + position as u64, e))?;
+        chunks.push(chunk);
+    }
+    Ok((chunks, cursor.position() as usize))
+}
+
+/// Writes the `"FOR1"` header followed by `payload` (i.e., the encoded chunks).
+pub(crate) fn write_payload<W: Write>(mut writer: W, payload: &[u8]) -> Result<()> {
+    let payload_size = payload.len().saturating_add(4);
+    let payload_size = u32::try_from(payload_size).map_err(|_| Error::TooLarge {
+        what: "payload size",
+        size: payload_size as u64,
+    })?;
+    Header::new(payload_size).to_writer(&mut writer)?;
+    writer.write_all(payload)?;
+    Ok(())
+}
+
+/// Writes the `"FOR1"` header, `chunks` and `trailing_payload` to `writer` by seeking back
+/// to write the payload size.
+pub(crate) fn write_seekable<W, C>(
+    mut writer: W,
+    chunks: &[C],
+    trailing_payload: &[u8],
+) -> Result<()>
+where
+    W: Write + Seek,
+    C: Chunk,
+{
+    let start = writer.stream_position()?;
+    Header::new(0).to_writer(&mut writer)?;
+    for chunk in chunks {
+        chunk.encode_seekable(&mut writer)?;
+    }
+    writer.write_all(trailing_payload)?;
+    let end = writer.stream_position()?;
+
+    let payload_size = end - start - 8;
+    let payload_size = u32::try_from(payload_size).map_err(|_| Error::TooLarge {
+        what: "payload size",
+        size: payload_size,
+    })?;
+    writer.seek(SeekFrom::Start(start + 4))?;
+    writer.write_u32(payload_size)?;
+    writer.seek(SeekFrom::Start(end))?;
+    Ok(())
+}
+
+/// The size of the `"FOR1"` header in bytes.
+pub(crate) const HEADER_SIZE: u64 = 12;
+
+/// The size of a chunk header (i.e., the identifier and data size) in bytes.
+pub(crate) const CHUNK_HEADER_SIZE: u64 = 8;
+
+pub(crate) struct Header {
+    pub magic_number: [u8; 4],
+    pub payload_size: u32,
+    pub type_id: [u8; 4],
 }
 impl Header {
-    fn new(payload_size: u32) -> Self {
+    pub fn new(payload_size: u32) -> Self {
         Header {
             magic_number: *b"FOR1",
             payload_size,
             type_id: *b"BEAM",
         }
     }
-    fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
         let mut header = Self::new(0);
         reader.read_exact(&mut header.magic_number)?;
-        header.payload_size = reader.read_u32::<BigEndian>()?;
+        header.payload_size = reader.read_u32()?;
         reader.read_exact(&mut header.type_id)?;
         Ok(header)
     }
-    fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+    pub fn check(&self) -> Result<()> {
+        let expected = Self::new(0);
+        if self.magic_number != expected.magic_number {
+            return Err(Error::UnexpectedMagicNumber {
+                magic_number: self.magic_number,
+            });
+        }
+        if self.type_id != expected.type_id {
+            return Err(Error::UnexpectedFormType {
+                form_type: self.type_id,
+            });
+        }
+        if self.payload_size < 4 {
+            return Err(Error::TruncatedPayload {
+                expected: 4,
+                actual: u64::from(self.payload_size),
+            });
+        }
+        Ok(())
+    }
+    /// Returns the size of the chunks part of the payload (i.e., the payload without the form type)
+    /// after checking the file size against `limits`.
+    pub fn chunks_size(&self, limits: &DecodeLimits) -> Result<u64> {
+        limits.check(Limit::FileSize, u64::from(self.payload_size) + 8)?;
+        Ok(u64::from(self.payload_size - 4))
+    }
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
         writer.write_all(&self.magic_number)?;
-        writer.write_u32::<BigEndian>(self.payload_size)?;
+        writer.write_u32(self.payload_size)?;
         writer.write_all(&self.type_id)?;
         Ok(())
     }
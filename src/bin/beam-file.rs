@@ -0,0 +1,186 @@
+//! A command-line tool for inspecting and editing BEAM files.
+use beam_file::chunk::{Chunk, Id, RawChunk, StandardChunk};
+use beam_file::{verify, BeamInfo, RawBeamFile, StandardBeamFile};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: beam-file <COMMAND> <ARGS>
+
+Commands:
+  info <FILE>                           Show the module name, sizes and chunk table
+  chunks <FILE> [<ID>] [--hex]          Dump the chunks (all by default) as decoded structures or hex
+  extract <FILE> <ID> <OUTPUT>          Write the data of a chunk to OUTPUT (`-` for stdout)
+  replace <FILE> <ID> <INPUT> [-o OUT]  Replace (or append) a chunk with the contents of INPUT
+  json <FILE>                           Dump the whole file as JSON
+  verify <FILE>                         Check the bytecode and print the violations found
+";
+
+type CliResult = Result<(), Box<dyn std::error::Error>>;
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let result = match args[..] {
+        ["info", file] => info(file),
+        ["chunks", file] => chunks(file, None, false),
+        ["chunks", file, "--hex"] => chunks(file, None, true),
+        ["chunks", file, id] => chunks(file, Some(id), false),
+        ["chunks", file, id, "--hex"] | ["chunks", file, "--hex", id] => {
+            chunks(file, Some(id), true)
+        }
+        ["extract", file, id, output] => extract(file, id, output),
+        ["replace", file, id, input] => replace(file, id, input, file),
+        ["replace", file, id, input, "-o", output] => replace(file, id, input, output),
+        ["json", file] => json(file),
+        ["verify", file] => verify(file),
+        ["help" | "-h" | "--help"] => {
+            print!("{}", USAGE);
+            return;
+        }
+        _ => {
+            eprint!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn info(file: &str) -> CliResult {
+    let info = BeamInfo::from_file(file)?;
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "module:       {}",
+        info.module.as_deref().unwrap_or("-")
+    )?;
+    writeln!(out, "file size:    {}", info.file_size())?;
+    writeln!(out, "payload size: {}", info.payload_size)?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:<4}  {:>8}  {:>8}  {:>7}",
+        "ID", "OFFSET", "SIZE", "PADDING"
+    )?;
+    for chunk in &info.chunks {
+        writeln!(
+            out,
+            "{:<4}  {:>8}  {:>8}  {:>7}",
+            String::from_utf8_lossy(&chunk.id),
+            chunk.offset,
+            chunk.size,
+            chunk.padding
+        )?;
+    }
+    Ok(())
+}
+
+fn chunks(file: &str, id: Option<&str>, hex: bool) -> CliResult {
+    let beam = RawBeamFile::from_file(file)?;
+    let id = id.map(parse_id).transpose()?;
+    let mut out = io::stdout().lock();
+    let mut found = false;
+    for chunk in beam
+        .chunks
+        .iter()
+        .filter(|c| id.is_none_or(|id| c.id == id))
+    {
+        found = true;
+        writeln!(out, "== {}", String::from_utf8_lossy(&chunk.id))?;
+        if hex {
+            out.write_all(hex_dump(&chunk.data).as_bytes())?;
+        } else {
+            let decoded = StandardChunk::decode_data(&chunk.id, &chunk.data[..])?;
+            writeln!(out, "{:#?}", decoded)?;
+        }
+    }
+    match id {
+        Some(id) if !found => Err(no_such_chunk(&id)),
+        _ => Ok(()),
+    }
+}
+
+fn extract(file: &str, id: &str, output: &str) -> CliResult {
+    let beam = RawBeamFile::from_file(file)?;
+    let id = parse_id(id)?;
+    let chunk = beam.chunk_by_id(&id).ok_or_else(|| no_such_chunk(&id))?;
+    if output == "-" {
+        io::stdout().lock().write_all(&chunk.data)?;
+    } else {
+        std::fs::write(output, &chunk.data)?;
+    }
+    Ok(())
+}
+
+fn replace(file: &str, id: &str, input: &str, output: &str) -> CliResult {
+    let mut beam = RawBeamFile::from_file(file)?;
+    let id = parse_id(id)?;
+    let data = std::fs::read(input)?;
+    match beam.chunk_by_id_mut(&id) {
+        Some(chunk) => chunk.data = data,
+        None => beam.chunks.push(RawChunk { id, data }),
+    }
+    beam.to_file(output)?;
+    Ok(())
+}
+
+fn json(file: &str) -> CliResult {
+    let beam = StandardBeamFile::from_file(file)?;
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, &beam)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn verify(file: &str) -> CliResult {
+    let beam = StandardBeamFile::from_file(file)?;
+    let violations = verify::check(&beam)?;
+    let mut out = io::stdout().lock();
+    for violation in &violations {
+        writeln!(out, "{}", violation)?;
+    }
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} violation(s) found", violations.len()).into())
+    }
+}
+
+fn parse_id(id: &str) -> Result<Id, Box<dyn std::error::Error>> {
+    id.as_bytes()
+        .try_into()
+        .map_err(|_| format!("chunk identifier must be 4 bytes: {:?}", id).into())
+}
+
+fn no_such_chunk(id: &Id) -> Box<dyn std::error::Error> {
+    format!("no such chunk: {:?}", String::from_utf8_lossy(id)).into()
+}
+
+/// Formats `data` in the same layout as `xxd`.
+fn hex_dump(data: &[u8]) -> String {
+    let mut s = String::new();
+    for (i, line) in data.chunks(16).enumerate() {
+        let _ = write!(s, "{:08x}:", i * 16);
+        for (j, b) in line.iter().enumerate() {
+            if j % 2 == 0 {
+                s.push(' ');
+            }
+            let _ = write!(s, "{:02x}", b);
+        }
+        let width = 40 - (line.len() * 2 + line.len().div_ceil(2));
+        s.extend(std::iter::repeat_n(' ', width + 2));
+        s.extend(line.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        s.push('\n');
+    }
+    s
+}
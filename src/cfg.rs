@@ -0,0 +1,323 @@
+//! Control-flow graphs of the functions in the `"Code"` chunk.
+//!
+//! A basic block starts at a label or after an instruction which may branch,
+//! and the edges are derived from the labels in the operands (e.g., the fail labels of tests,
+//! the jump tables of `select_val` and the handlers of `try`).
+//! Exceptions raised by other instructions are not represented as edges.
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+use core::ops::Range;
+
+use crate::code::{Function, Instruction, Operand};
+use crate::io::Write;
+use crate::{Disassembler, Error, Result};
+
+/// Instructions which leave the function (i.e., `return` and tail calls).
+const EXITS: &[&str] = &[
+    "return",
+    "call_only",
+    "call_last",
+    "call_ext_only",
+    "call_ext_last",
+    "apply_last",
+];
+
+/// Instructions after which the execution never continues to the next instruction
+/// except for `EXITS` and jumps.
+const NO_FALL_THROUGH: &[&str] = &[
+    "func_info",
+    "badmatch",
+    "case_end",
+    "if_end",
+    "try_case_end",
+    "badrecord",
+    "raise",
+    "select_val",
+    "select_tuple_arity",
+    "jump_on_val",
+    "int_code_end",
+];
+
+/// Instructions whose labels are not the targets of branches.
+const NON_BRANCHING: &[&str] = &["call", "call_last", "call_only", "recv_mark", "recv_set"];
+
+/// The control-flow graph of a function.
+///
+/// ```
+/// use beam_file::StandardBeamFile;
+/// use beam_file::cfg::{ControlFlowGraph, EdgeKind};
+/// use beam_file::code;
+///
+/// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let hello = &code::functions(&beam).unwrap()[0];
+/// let cfg = ControlFlowGraph::new(hello).unwrap();
+///
+/// // The `func_info` block and the body
+/// assert_eq!(2, cfg.blocks.len());
+/// assert_eq!(vec![2], cfg.blocks[cfg.entry].labels);
+/// assert_eq!(None, cfg.blocks[cfg.entry].edges[0].target);
+/// assert_eq!(EdgeKind::Return, cfg.blocks[cfg.entry].edges[0].kind);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+
+    /// The index of the block which starts with the entry label.
+    pub entry: usize,
+}
+impl ControlFlowGraph {
+    /// Builds the control-flow graph of `function`.
+    pub fn new(function: &Function) -> Result<Self> {
+        let instructions = &function.instructions;
+
+        // Splits the instructions at the leaders
+        let mut blocks = Vec::<BasicBlock>::new();
+        for (i, instruction) in instructions.iter().enumerate() {
+            let is_label = instruction.name() == "label";
+            let starts_block = match i.checked_sub(1).map(|j| &instructions[j]) {
+                None => true,
+                Some(prev) if prev.name() == "label" => false,
+                Some(prev) => is_label || ends_block(prev),
+            };
+            if starts_block {
+                blocks.push(BasicBlock {
+                    labels: Vec::new(),
+                    range: i..i,
+                    edges: Vec::new(),
+                });
+            }
+            let block = blocks.last_mut().expect("no block");
+            block.range.end = i + 1;
+            if let ("label", [Operand::Unsigned(label)]) =
+                (instruction.name(), &instruction.args[..])
+            {
+                block.labels.push(*label as u32);
+            }
+        }
+        let block_of: BTreeMap<u32, usize> = blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, b)| b.labels.iter().map(move |&l| (l, i)))
+            .collect();
+
+        for i in 0..blocks.len() {
+            let last = blocks[i].range.end - 1;
+            let target = |label: u32| {
+                block_of
+                    .get(&label)
+                    .copied()
+                    .ok_or(Error::InvalidInstruction {
+                        offset: function.offsets[last],
+                        reason: "unknown label",
+                    })
+            };
+            let mut edges = Vec::new();
+            for (label, kind) in branches(&instructions[last]) {
+                edges.push(Edge {
+                    target: Some(target(label)?),
+                    kind,
+                });
+            }
+            let name = instructions[last].name();
+            if EXITS.contains(&name) {
+                edges.push(Edge {
+                    target: None,
+                    kind: EdgeKind::Return,
+                });
+            } else if !NO_FALL_THROUGH.contains(&name)
+                && !matches!(name, "jump" | "loop_rec_end" | "wait")
+                && i + 1 < blocks.len()
+            {
+                edges.push(Edge {
+                    target: Some(i + 1),
+                    kind: EdgeKind::Next,
+                });
+            }
+            let mut unique = Vec::with_capacity(edges.len());
+            for edge in edges {
+                if !unique.contains(&edge) {
+                    unique.push(edge);
+                }
+            }
+            blocks[i].edges = unique;
+        }
+
+        let entry = block_of
+            .get(&function.entry)
+            .copied()
+            .ok_or(Error::InvalidInstruction {
+                offset: function.range.start,
+                reason: "unknown entry label",
+            })?;
+        Ok(ControlFlowGraph { blocks, entry })
+    }
+
+    /// Returns the indices of the blocks which have an edge to `block`.
+    pub fn predecessors(&self, block: usize) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&i| self.blocks[i].edges.iter().any(|e| e.target == Some(block)))
+            .collect()
+    }
+
+    /// Writes the graph in the Graphviz DOT language.
+    ///
+    /// `function` must be the one the graph was built from,
+    /// and `disasm` is used to print the instructions in the blocks.
+    pub fn write_dot<W: Write>(
+        &self,
+        mut writer: W,
+        function: &Function,
+        disasm: &Disassembler,
+    ) -> Result<()> {
+        let mut out = String::new();
+        let title = alloc::format!("{}/{}", function.name, function.arity);
+        let _ = writeln!(out, "digraph {} {{", dot_string(&title));
+        let _ = writeln!(out, "  node [shape=box, fontname=\"monospace\"];");
+        for (i, block) in self.blocks.iter().enumerate() {
+            let mut label = String::new();
+            for i in block.range.clone() {
+                let instruction =
+                    disasm.instruction(&function.instructions[i], function.offsets[i])?;
+                let _ = writeln!(label, "{}", instruction);
+            }
+            let _ = write!(out, "  b{} [label={}", i, dot_string(&label));
+            if i == self.entry {
+                let _ = write!(out, ", penwidth=2");
+            }
+            let _ = writeln!(out, "];");
+        }
+        if self.edges().any(|(_, e)| e.target.is_none()) {
+            let _ = writeln!(out, "  exit [shape=doublecircle, label=\"\"];");
+        }
+        for (i, edge) in self.edges() {
+            let _ = write!(out, "  b{} -> ", i);
+            match edge.target {
+                Some(target) => {
+                    let _ = write!(out, "b{}", target);
+                }
+                None => out.push_str("exit"),
+            }
+            let _ = match edge.kind {
+                EdgeKind::Next | EdgeKind::Return => writeln!(out, ";"),
+                EdgeKind::Catch => writeln!(out, " [label=\"catch\", style=dashed];"),
+                kind => writeln!(out, " [label=\"{}\"];", kind.name()),
+            };
+        }
+        out.push_str("}\n");
+        writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (usize, &Edge)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, b)| b.edges.iter().map(move |e| (i, e)))
+    }
+}
+
+/// A basic block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The labels at the start of the block.
+    pub labels: Vec<u32>,
+
+    /// The range of the block in `Function::instructions`.
+    pub range: Range<usize>,
+
+    pub edges: Vec<Edge>,
+}
+
+/// An edge from a basic block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    /// The index of the target block, or `None` if the edge leaves the function.
+    pub target: Option<usize>,
+
+    pub kind: EdgeKind,
+}
+
+/// The kind of an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// Falls through to the next block.
+    Next,
+
+    /// An unconditional jump (e.g., `jump` and `loop_rec_end`).
+    Jump,
+
+    /// A branch taken when a test or a BIF fails.
+    Fail,
+
+    /// An entry of the jump table of `select_val` or `select_tuple_arity`.
+    Select,
+
+    /// The exception handler set by `try` or `catch`.
+    Catch,
+
+    /// `return` or a tail call.
+    Return,
+}
+impl EdgeKind {
+    fn name(self) -> &'static str {
+        match self {
+            EdgeKind::Next => "next",
+            EdgeKind::Jump => "jump",
+            EdgeKind::Fail => "fail",
+            EdgeKind::Select => "select",
+            EdgeKind::Catch => "catch",
+            EdgeKind::Return => "return",
+        }
+    }
+}
+
+fn ends_block(instruction: &Instruction) -> bool {
+    let name = instruction.name();
+    EXITS.contains(&name) || NO_FALL_THROUGH.contains(&name) || !branches(instruction).is_empty()
+}
+
+/// Returns the labels which `instruction` may branch to.
+fn branches(instruction: &Instruction) -> Vec<(u32, EdgeKind)> {
+    let name = instruction.name();
+    if NON_BRANCHING.contains(&name) {
+        return Vec::new();
+    }
+    let kind = match name {
+        "jump" | "loop_rec_end" | "wait" | "wait_timeout" => EdgeKind::Jump,
+        "try" | "catch" => EdgeKind::Catch,
+        _ => EdgeKind::Fail,
+    };
+    let mut branches = Vec::new();
+    for arg in &instruction.args {
+        match *arg {
+            // `{f,0}` means that an exception is raised instead
+            Operand::Label(0) => {}
+            Operand::Label(label) => branches.push((label, kind)),
+            Operand::List(ref elements) => {
+                branches.extend(elements.iter().filter_map(|e| match *e {
+                    Operand::Label(label) => Some((label, EdgeKind::Select)),
+                    _ => None,
+                }))
+            }
+            _ => {}
+        }
+    }
+    branches
+}
+
+/// Quotes `s` as a DOT string, where a newline left-justifies the preceding line.
+fn dot_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\l"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
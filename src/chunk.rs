@@ -5,13 +5,18 @@
 //! - [`beam_lib`](http://erlang.org/doc/man/beam_lib.html)
 //!
 //! [BEAM]: http://rnyingma.synrc.com/publications/cat/Functional%20Languages/Erlang/BEAM.pdf
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str;
 use libflate::zlib;
-use std::io::{Cursor, Read, Write};
-use std::str;
 
+use crate::code;
+use crate::io::{Cursor, Read, Seek, SeekFrom, Write};
+use crate::io_ext::{ReadExt, WriteExt};
 use crate::parts;
-use crate::Result;
+use crate::{DecodeLimits, Error, Limit, Result};
 
 /// The identifier which indicates the type of a chunk.
 pub type Id = [u8; 4];
@@ -22,18 +27,26 @@ pub trait Chunk {
     fn id(&self) -> &Id;
 
     /// Reads a chunk from `reader`.
-    fn decode<R: Read>(mut reader: R) -> Result<Self>
+    fn decode<R: Read>(reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_with_limits(reader, &DecodeLimits::default())
+    }
+
+    /// Reads a chunk from `reader` within `limits`.
+    fn decode_with_limits<R: Read>(mut reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
         let header = aux::Header::decode(&mut reader)?;
-        let mut buf = vec![0; header.data_size as usize];
-        reader.read_exact(&mut buf)?;
+        limits.check(Limit::ChunkSize, u64::from(header.data_size))?;
+        let buf = aux::read_bytes(&mut reader, header.data_size as usize)?;
         for _ in 0..aux::padding_size(header.data_size) {
             reader.read_u8()?;
         }
 
-        Self::decode_data(&header.chunk_id, Cursor::new(&buf))
+        Self::decode_data_with_limits(&header.chunk_id, Cursor::new(&buf), limits)
     }
 
     /// Reads a chunk which has the identifier `id` from `reader`.
@@ -43,13 +56,48 @@ pub trait Chunk {
     where
         Self: Sized;
 
+    /// Reads a chunk which has the identifier `id` from `reader` within `limits`.
+    ///
+    /// The default implementation ignores `limits` and calls `decode_data`.
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let _ = limits;
+        Self::decode_data(id, reader)
+    }
+
     /// Writes the chunk to `writer`.
     fn encode<W: Write>(&self, mut writer: W) -> Result<()> {
         let mut buf = Vec::new();
         self.encode_data(&mut buf)?;
-        aux::Header::new(self.id(), buf.len() as u32).encode(&mut writer)?;
+        let data_size = aux::to_u32("chunk size", buf.len())?;
+        aux::Header::new(self.id(), data_size).encode(&mut writer)?;
         writer.write_all(&buf)?;
-        for _ in 0..aux::padding_size(buf.len() as u32) {
+        for _ in 0..aux::padding_size(data_size) {
+            writer.write_u8(0)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the chunk to `writer` without buffering the data.
+    ///
+    /// The data size in the chunk header is written after the data by seeking back.
+    fn encode_seekable<W: Write + Seek>(&self, mut writer: W) -> Result<()> {
+        let start = writer.stream_position()?;
+        aux::Header::new(self.id(), 0).encode(&mut writer)?;
+        self.encode_data(&mut writer)?;
+        let end = writer.stream_position()?;
+
+        let data_size = end - start - 8;
+        let data_size = u32::try_from(data_size).map_err(|_| Error::TooLarge {
+            what: "chunk size",
+            size: data_size,
+        })?;
+        writer.seek(SeekFrom::Start(start + 4))?;
+        writer.write_u32(data_size)?;
+        writer.seek(SeekFrom::Start(end))?;
+        for _ in 0..aux::padding_size(data_size) {
             writer.write_u8(0)?;
         }
         Ok(())
@@ -62,18 +110,56 @@ pub trait Chunk {
     fn encode_data<W: Write>(&self, writer: W) -> Result<()>;
 }
 
+/// The `TypedChunk` trait links a typed chunk to the identifiers it is stored under.
+pub trait TypedChunk: Chunk {
+    /// The identifiers of the chunk.
+    ///
+    /// A chunk may have several identifiers (e.g., `"Atom"` and `"AtU8"`).
+    const IDS: &'static [Id];
+}
+
+/// The `AsChunk` trait represents a chunk type which may hold a typed chunk `T`.
+pub trait AsChunk<T: TypedChunk> {
+    /// Returns a reference to the typed chunk if `self` holds it.
+    fn as_chunk(&self) -> Option<&T>;
+
+    /// Returns a mutable reference to the typed chunk if `self` holds it.
+    fn as_chunk_mut(&mut self) -> Option<&mut T>;
+
+    /// Makes a chunk which holds `chunk`.
+    fn from_chunk(chunk: T) -> Self;
+}
+
 /// A raw representation of a chunk.
 ///
 /// This implementation does not interpret the data of a chunk
 /// at the time of reading it from a BEAM file.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RawChunk {
     /// The identifier of the chunk.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::id"))]
     pub id: Id,
 
     /// The bare data of the chunk.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub data: Vec<u8>,
 }
+impl RawChunk {
+    /// Decodes the data of the chunk as `C`.
+    ///
+    /// ```
+    /// use beam_file::RawBeamFile;
+    /// use beam_file::chunk::{AtomChunk, Chunk};
+    ///
+    /// let beam = RawBeamFile::from_file("tests/testdata/test.beam").unwrap();
+    /// let atoms: AtomChunk = beam.chunks[0].decode_as().unwrap();
+    /// assert_eq!("test", atoms.atoms[0].name);
+    /// ```
+    pub fn decode_as<C: Chunk>(&self) -> Result<C> {
+        C::decode_data(&self.id, &self.data[..])
+    }
+}
 impl Chunk for RawChunk {
     fn id(&self) -> &Id {
         &self.id
@@ -94,12 +180,37 @@ impl Chunk for RawChunk {
 
 /// A representation of the `"Atom"` and `"AtU8"` chunks.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtomChunk {
     // Whether or not this Atom chunk contains UTF-8 atoms
     pub is_unicode: bool,
     /// The list of atoms contained in a BEAM file.
     pub atoms: Vec<parts::Atom>,
 }
+impl AtomChunk {
+    /// Returns the atom identified by `id` (i.e., the one-based index of `atoms`).
+    ///
+    /// ```
+    /// use beam_file::StandardBeamFile;
+    /// use beam_file::chunk::AtomChunk;
+    ///
+    /// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+    /// let atoms = beam.chunk::<AtomChunk>().unwrap();
+    /// assert_eq!("test", atoms.get(1).unwrap().name);
+    /// assert!(atoms.get(0).is_none());
+    /// ```
+    pub fn get(&self, id: parts::AtomId) -> Option<&parts::Atom> {
+        let index = (id as usize).checked_sub(1)?;
+        self.atoms.get(index)
+    }
+
+    /// Same as `get`, but returns `Error::UnknownAtom` if there is no such atom.
+    pub fn name(&self, id: parts::AtomId) -> Result<&str> {
+        self.get(id)
+            .map(|a| a.name.as_str())
+            .ok_or(Error::UnknownAtom { id })
+    }
+}
 impl Chunk for AtomChunk {
     fn id(&self) -> &Id {
         if self.is_unicode {
@@ -108,7 +219,13 @@ impl Chunk for AtomChunk {
             b"Atom"
         }
     }
-    fn decode_data<R: Read>(id: &Id, mut reader: R) -> Result<Self>
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
@@ -120,17 +237,18 @@ impl Chunk for AtomChunk {
             }
             Ok(_) => false,
         };
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut atoms = Vec::with_capacity(count);
-        for _ in 0..count {
-            let len = reader.read_u8()? as usize;
-            let mut buf = vec![0; len];
-            reader.read_exact(&mut buf)?;
-
-            let name = str::from_utf8(&buf).map(|s| s.to_string())?;
-            atoms.push(parts::Atom {
-                name: name.to_string(),
-            });
+        let mut reader = aux::CountingReader::new(reader);
+        let count = aux::read_count(&mut reader, limits)?;
+        let mut atoms = Vec::with_capacity(aux::capacity(count));
+        for index in 0..count {
+            let atom = aux::decode_entry(index, reader.position, || {
+                let len = reader.read_u8()? as usize;
+                let buf = aux::read_bytes(&mut reader, len)?;
+
+                let name = str::from_utf8(&buf).map(|s| s.to_string())?;
+                Ok(parts::Atom { name })
+            })?;
+            atoms.push(atom);
         }
         Ok(AtomChunk {
             is_unicode: unicode,
@@ -138,9 +256,14 @@ impl Chunk for AtomChunk {
         })
     }
     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
-        writer.write_u32::<BigEndian>(self.atoms.len() as u32)?;
-        for atom in &self.atoms {
-            assert!(atom.name.len() < 0x100);
+        writer.write_u32(aux::to_u32("atom count", self.atoms.len())?)?;
+        for (index, atom) in self.atoms.iter().enumerate() {
+            if atom.name.len() > 0xFF {
+                return Err(Error::AtomTooLong {
+                    index,
+                    len: atom.name.len(),
+                });
+            }
             writer.write_u8(atom.name.len() as u8)?;
             writer.write_all(atom.name.as_bytes())?;
         }
@@ -150,6 +273,7 @@ impl Chunk for AtomChunk {
 
 /// A representation of the `"Code"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CodeChunk {
     /// Length of the information fields before code.
     pub info_size: u32,
@@ -167,6 +291,7 @@ pub struct CodeChunk {
     pub function_count: u32,
 
     /// The byte code.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub bytecode: Vec<u8>,
 }
 impl Chunk for CodeChunk {
@@ -179,22 +304,22 @@ impl Chunk for CodeChunk {
     {
         aux::check_chunk_id(id, b"Code")?;
         let mut code = CodeChunk {
-            info_size: reader.read_u32::<BigEndian>()?,
-            version: reader.read_u32::<BigEndian>()?,
-            opcode_max: reader.read_u32::<BigEndian>()?,
-            label_count: reader.read_u32::<BigEndian>()?,
-            function_count: reader.read_u32::<BigEndian>()?,
+            info_size: reader.read_u32()?,
+            version: reader.read_u32()?,
+            opcode_max: reader.read_u32()?,
+            label_count: reader.read_u32()?,
+            function_count: reader.read_u32()?,
             bytecode: Vec::new(),
         };
         reader.read_to_end(&mut code.bytecode)?;
         Ok(code)
     }
     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
-        writer.write_u32::<BigEndian>(self.info_size)?;
-        writer.write_u32::<BigEndian>(self.version)?;
-        writer.write_u32::<BigEndian>(self.opcode_max)?;
-        writer.write_u32::<BigEndian>(self.label_count)?;
-        writer.write_u32::<BigEndian>(self.function_count)?;
+        writer.write_u32(self.info_size)?;
+        writer.write_u32(self.version)?;
+        writer.write_u32(self.opcode_max)?;
+        writer.write_u32(self.label_count)?;
+        writer.write_u32(self.function_count)?;
         writer.write_all(&self.bytecode)?;
         Ok(())
     }
@@ -202,8 +327,10 @@ impl Chunk for CodeChunk {
 
 /// A representation of the `"StrT"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StrTChunk {
     /// Concatenated strings.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub strings: Vec<u8>,
 }
 impl Chunk for StrTChunk {
@@ -227,6 +354,7 @@ impl Chunk for StrTChunk {
 
 /// A representation of the `"ImpT"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImpTChunk {
     /// The list of imported functions.
     pub imports: Vec<parts::Import>,
@@ -235,28 +363,38 @@ impl Chunk for ImpTChunk {
     fn id(&self) -> &Id {
         b"ImpT"
     }
-    fn decode_data<R: Read>(id: &Id, mut reader: R) -> Result<Self>
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
         aux::check_chunk_id(id, b"ImpT")?;
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut imports = Vec::with_capacity(count);
-        for _ in 0..count {
-            imports.push(parts::Import {
-                module: reader.read_u32::<BigEndian>()?,
-                function: reader.read_u32::<BigEndian>()?,
-                arity: reader.read_u32::<BigEndian>()?,
-            });
+        let mut reader = aux::CountingReader::new(reader);
+        let count = aux::read_count(&mut reader, limits)?;
+        let mut imports = Vec::with_capacity(aux::capacity(count));
+        for index in 0..count {
+            let import = aux::decode_entry(index, reader.position, || {
+                Ok(parts::Import {
+                    module: reader.read_u32()?,
+                    function: reader.read_u32()?,
+                    arity: reader.read_u32()?,
+                })
+            })?;
+            imports.push(import);
         }
         Ok(ImpTChunk { imports })
     }
     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
-        writer.write_u32::<BigEndian>(self.imports.len() as u32)?;
+        writer.write_u32(aux::to_u32("import count", self.imports.len())?)?;
         for import in &self.imports {
-            writer.write_u32::<BigEndian>(import.module)?;
-            writer.write_u32::<BigEndian>(import.function)?;
-            writer.write_u32::<BigEndian>(import.arity)?;
+            writer.write_u32(import.module)?;
+            writer.write_u32(import.function)?;
+            writer.write_u32(import.arity)?;
         }
         Ok(())
     }
@@ -264,6 +402,7 @@ impl Chunk for ImpTChunk {
 
 /// A representation of the `"ExpT"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExpTChunk {
     /// The list of exported functions.
     pub exports: Vec<parts::Export>,
@@ -272,28 +411,38 @@ impl Chunk for ExpTChunk {
     fn id(&self) -> &Id {
         b"ExpT"
     }
-    fn decode_data<R: Read>(id: &Id, mut reader: R) -> Result<Self>
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
         aux::check_chunk_id(id, b"ExpT")?;
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut exports = Vec::with_capacity(count);
-        for _ in 0..count {
-            exports.push(parts::Export {
-                function: reader.read_u32::<BigEndian>()?,
-                arity: reader.read_u32::<BigEndian>()?,
-                label: reader.read_u32::<BigEndian>()?,
-            });
+        let mut reader = aux::CountingReader::new(reader);
+        let count = aux::read_count(&mut reader, limits)?;
+        let mut exports = Vec::with_capacity(aux::capacity(count));
+        for index in 0..count {
+            let export = aux::decode_entry(index, reader.position, || {
+                Ok(parts::Export {
+                    function: reader.read_u32()?,
+                    arity: reader.read_u32()?,
+                    label: reader.read_u32()?,
+                })
+            })?;
+            exports.push(export);
         }
         Ok(ExpTChunk { exports })
     }
     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
-        writer.write_u32::<BigEndian>(self.exports.len() as u32)?;
+        writer.write_u32(aux::to_u32("export count", self.exports.len())?)?;
         for export in &self.exports {
-            writer.write_u32::<BigEndian>(export.function)?;
-            writer.write_u32::<BigEndian>(export.arity)?;
-            writer.write_u32::<BigEndian>(export.label)?;
+            writer.write_u32(export.function)?;
+            writer.write_u32(export.arity)?;
+            writer.write_u32(export.label)?;
         }
         Ok(())
     }
@@ -301,32 +450,49 @@ impl Chunk for ExpTChunk {
 
 /// A representation of the `"LitT"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LitTChunk {
     /// The list of literal terms.
     ///
     /// Each term is encoded in the [External Term Format]
     /// (http://erlang.org/doc/apps/erts/erl_ext_dist.html).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex_list"))]
     pub literals: Vec<parts::ExternalTermFormatBinary>,
 }
 impl Chunk for LitTChunk {
     fn id(&self) -> &Id {
         b"LitT"
     }
-    fn decode_data<R: Read>(id: &Id, mut reader: R) -> Result<Self>
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(
+        id: &Id,
+        mut reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self>
     where
         Self: Sized,
     {
         aux::check_chunk_id(id, b"LitT")?;
-        let _uncompressed_size = reader.read_u32::<BigEndian>()?;
-        let mut decoder = zlib::Decoder::new(reader)?;
+        let uncompressed_size = reader.read_u32()?;
+        limits.check(Limit::LiteralSize, u64::from(uncompressed_size))?;
+        let mut decoder = aux::CountingReader::new(zlib::Decoder::new(reader)?);
 
-        let count = decoder.read_u32::<BigEndian>()? as usize;
-        let mut literals = Vec::with_capacity(count);
-        for _ in 0..count {
-            let literal_size = decoder.read_u32::<BigEndian>()? as usize;
-            let mut buf = vec![0; literal_size];
-            decoder.read_exact(&mut buf)?;
-            literals.push(buf);
+        let count = aux::read_count(&mut decoder, limits)?;
+        let mut literals = Vec::with_capacity(aux::capacity(count));
+        let mut total_size = 4;
+        for index in 0..count {
+            let literal = aux::decode_entry(index, decoder.position, || {
+                let literal_size = decoder.read_u32()?;
+                total_size += 4 + u64::from(literal_size);
+                limits.check(Limit::LiteralSize, total_size)?;
+                Ok(aux::read_bytes(&mut decoder, literal_size as usize)?)
+            })?;
+            literals.push(literal);
         }
         Ok(LitTChunk { literals })
     }
@@ -334,13 +500,14 @@ impl Chunk for LitTChunk {
         let uncompressed_size = self
             .literals
             .iter()
-            .fold(4, |acc, l| acc + 4 + l.len() as u32);
-        writer.write_u32::<BigEndian>(uncompressed_size)?;
+            .try_fold(4usize, |acc, l| acc.checked_add(4)?.checked_add(l.len()))
+            .unwrap_or(usize::MAX);
+        writer.write_u32(aux::to_u32("literal size", uncompressed_size)?)?;
 
         let mut encoder = zlib::Encoder::new(writer)?;
-        encoder.write_u32::<BigEndian>(self.literals.len() as u32)?;
+        encoder.write_u32(aux::to_u32("literal count", self.literals.len())?)?;
         for literal in &self.literals {
-            encoder.write_u32::<BigEndian>(literal.len() as u32)?;
+            encoder.write_u32(aux::to_u32("literal size", literal.len())?)?;
             encoder.write_all(literal)?;
         }
         encoder.finish().into_result()?;
@@ -350,6 +517,7 @@ impl Chunk for LitTChunk {
 
 /// A representation of the `"LocT"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocTChunk {
     /// The list of local functions.
     pub locals: Vec<parts::Local>,
@@ -358,28 +526,38 @@ impl Chunk for LocTChunk {
     fn id(&self) -> &Id {
         b"LocT"
     }
-    fn decode_data<R: Read>(id: &Id, mut reader: R) -> Result<Self>
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
         aux::check_chunk_id(id, b"LocT")?;
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut locals = Vec::with_capacity(count);
-        for _ in 0..count {
-            locals.push(parts::Local {
-                function: reader.read_u32::<BigEndian>()?,
-                arity: reader.read_u32::<BigEndian>()?,
-                label: reader.read_u32::<BigEndian>()?,
-            });
+        let mut reader = aux::CountingReader::new(reader);
+        let count = aux::read_count(&mut reader, limits)?;
+        let mut locals = Vec::with_capacity(aux::capacity(count));
+        for index in 0..count {
+            let local = aux::decode_entry(index, reader.position, || {
+                Ok(parts::Local {
+                    function: reader.read_u32()?,
+                    arity: reader.read_u32()?,
+                    label: reader.read_u32()?,
+                })
+            })?;
+            locals.push(local);
         }
         Ok(LocTChunk { locals })
     }
     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
-        writer.write_u32::<BigEndian>(self.locals.len() as u32)?;
+        writer.write_u32(aux::to_u32("local count", self.locals.len())?)?;
         for local in &self.locals {
-            writer.write_u32::<BigEndian>(local.function)?;
-            writer.write_u32::<BigEndian>(local.arity)?;
-            writer.write_u32::<BigEndian>(local.label)?;
+            writer.write_u32(local.function)?;
+            writer.write_u32(local.arity)?;
+            writer.write_u32(local.label)?;
         }
         Ok(())
     }
@@ -387,6 +565,7 @@ impl Chunk for LocTChunk {
 
 /// A representation of the `"FunT"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunTChunk {
     /// The list of anonymous functions.
     pub functions: Vec<parts::Function>,
@@ -395,34 +574,44 @@ impl Chunk for FunTChunk {
     fn id(&self) -> &Id {
         b"FunT"
     }
-    fn decode_data<R: Read>(id: &Id, mut reader: R) -> Result<Self>
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
         aux::check_chunk_id(id, b"FunT")?;
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut functions = Vec::with_capacity(count);
-        for _ in 0..count {
-            functions.push(parts::Function {
-                function: reader.read_u32::<BigEndian>()?,
-                arity: reader.read_u32::<BigEndian>()?,
-                label: reader.read_u32::<BigEndian>()?,
-                index: reader.read_u32::<BigEndian>()?,
-                num_free: reader.read_u32::<BigEndian>()?,
-                old_uniq: reader.read_u32::<BigEndian>()?,
-            });
+        let mut reader = aux::CountingReader::new(reader);
+        let count = aux::read_count(&mut reader, limits)?;
+        let mut functions = Vec::with_capacity(aux::capacity(count));
+        for index in 0..count {
+            let function = aux::decode_entry(index, reader.position, || {
+                Ok(parts::Function {
+                    function: reader.read_u32()?,
+                    arity: reader.read_u32()?,
+                    label: reader.read_u32()?,
+                    index: reader.read_u32()?,
+                    num_free: reader.read_u32()?,
+                    old_uniq: reader.read_u32()?,
+                })
+            })?;
+            functions.push(function);
         }
         Ok(FunTChunk { functions })
     }
     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
-        writer.write_u32::<BigEndian>(self.functions.len() as u32)?;
+        writer.write_u32(aux::to_u32("function count", self.functions.len())?)?;
         for f in &self.functions {
-            writer.write_u32::<BigEndian>(f.function)?;
-            writer.write_u32::<BigEndian>(f.arity)?;
-            writer.write_u32::<BigEndian>(f.label)?;
-            writer.write_u32::<BigEndian>(f.index)?;
-            writer.write_u32::<BigEndian>(f.num_free)?;
-            writer.write_u32::<BigEndian>(f.old_uniq)?;
+            writer.write_u32(f.function)?;
+            writer.write_u32(f.arity)?;
+            writer.write_u32(f.label)?;
+            writer.write_u32(f.index)?;
+            writer.write_u32(f.num_free)?;
+            writer.write_u32(f.old_uniq)?;
         }
         Ok(())
     }
@@ -430,6 +619,7 @@ impl Chunk for FunTChunk {
 
 /// A representation of the `"Attr"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttrChunk {
     /// The attributes of a module (i.e., BEAM file).
     ///
@@ -437,6 +627,7 @@ pub struct AttrChunk {
     /// ```erlang
     /// term_to_binary(Module:module_info(attributes)).
     /// ```
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub term: parts::ExternalTermFormatBinary,
 }
 impl Chunk for AttrChunk {
@@ -460,6 +651,7 @@ impl Chunk for AttrChunk {
 
 /// A representation of the `"CInf"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CInfChunk {
     /// The compile information of a module (i.e., BEAM file).
     ///
@@ -467,6 +659,7 @@ pub struct CInfChunk {
     /// ```erlang
     /// term_to_binary(Module:module_info(compile)).
     /// ```
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub term: parts::ExternalTermFormatBinary,
 }
 impl Chunk for CInfChunk {
@@ -490,12 +683,14 @@ impl Chunk for CInfChunk {
 
 /// A representation of the `"Abst"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AbstChunk {
     /// The abstract code of a module (i.e., BEAM file).
     ///
     /// The value is encoded in the [External Term Format]
     /// (http://erlang.org/doc/apps/erts/erl_ext_dist.html) and
     /// represents [The Abstract Format](http://erlang.org/doc/apps/erts/absform.html).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub term: parts::ExternalTermFormatBinary,
 }
 impl Chunk for AbstChunk {
@@ -519,6 +714,7 @@ impl Chunk for AbstChunk {
 
 /// A representation of the `"Dbgi"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DbgiChunk {
     /// The debug information for a module (i.e., BEAM file).
     ///
@@ -535,6 +731,7 @@ pub struct DbgiChunk {
     /// Where `Backend` is a module which implements `debug_info/4`, and is responsible for
     /// converting `Data` to different representations as described [here](http://erlang.org/doc/man/beam_lib.html#type-debug_info).
     /// Debug information can be used to reconstruct original source code.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub term: parts::ExternalTermFormatBinary,
 }
 impl Chunk for DbgiChunk {
@@ -558,6 +755,7 @@ impl Chunk for DbgiChunk {
 
 /// A representation of the `"Docs"` chunk.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DocsChunk {
     /// The 'Docs' chunk contains embedded module documentation, such as moduledoc/doc in Elixir
     ///
@@ -585,6 +783,7 @@ pub struct DocsChunk {
     ///         doc_element :: {{kind :: atom(), function :: atom(), arity}, Anno, signature, doc_content(), Metadata}
     /// ```
     ///
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::hex"))]
     pub term: parts::ExternalTermFormatBinary,
 }
 impl Chunk for DocsChunk {
@@ -606,6 +805,165 @@ impl Chunk for DocsChunk {
     }
 }
 
+/// A representation of the `"Line"` chunk.
+///
+/// This chunk is not a part of `StandardChunk`. Use it with `ExtendedChunk`
+/// or decode it from a `RawChunk`.
+///
+/// ```
+/// use beam_file::RawBeamFile;
+/// use beam_file::chunk::{Chunk, LineChunk};
+/// use beam_file::parts::Location;
+///
+/// let beam = RawBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let raw = beam.chunk_by_id(b"Line").unwrap();
+/// let line = LineChunk::decode_data(b"Line", &raw.data[..]).unwrap();
+/// let locations = line.locations();
+/// assert_eq!(None, locations[0]);
+/// assert_eq!(Some(Location { file: 0, line: 7 }), locations[1]);
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineChunk {
+    /// The version of the chunk format.
+    pub version: u32,
+
+    /// The flags (currently unused).
+    pub flags: u32,
+
+    /// The number of `line` instructions in the code.
+    pub instruction_count: u32,
+
+    /// The line numbers, each preceded by the file index if it differs from the previous one.
+    pub items: Vec<parts::LineItem>,
+
+    /// The names of the source files (the file index `N` refers to the `N-1`-th name).
+    pub file_names: Vec<String>,
+}
+impl LineChunk {
+    /// Returns the locations indexed by the operand of the `line` instruction.
+    ///
+    /// The location at index `0` is always `None` (i.e., unknown).
+    pub fn locations(&self) -> Vec<Option<parts::Location>> {
+        let mut file = 0;
+        let mut locations = vec![None];
+        for item in &self.items {
+            match *item {
+                parts::LineItem::File(index) => file = index,
+                parts::LineItem::Line(line) => locations.push(Some(parts::Location { file, line })),
+            }
+        }
+        locations
+    }
+}
+impl Chunk for LineChunk {
+    fn id(&self) -> &Id {
+        b"Line"
+    }
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(
+        id: &Id,
+        mut reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        aux::check_chunk_id(id, b"Line")?;
+        let version = reader.read_u32()?;
+        let flags = reader.read_u32()?;
+        let instruction_count = reader.read_u32()?;
+        let line_count = aux::read_count(&mut reader, limits)?;
+        let file_count = aux::read_count(&mut reader, limits)?;
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let mut items = Vec::with_capacity(aux::capacity(line_count));
+        let mut compact = code::CompactReader::new(&buf);
+        let mut lines = 0;
+        while lines < line_count {
+            let item = match compact.operand()? {
+                code::Operand::Integer(n) => {
+                    lines += 1;
+                    parts::LineItem::Line(u32::try_from(n).map_err(|_| {
+                        Error::InvalidInstruction {
+                            offset: compact.position,
+                            reason: "bad line number",
+                        }
+                    })?)
+                }
+                code::Operand::Atom(index) => parts::LineItem::File(index),
+                _ => {
+                    return Err(Error::InvalidInstruction {
+                        offset: compact.position,
+                        reason: "unexpected line item",
+                    })
+                }
+            };
+            items.push(item);
+        }
+
+        let mut rest = &buf[compact.position..];
+        let mut file_names = Vec::with_capacity(aux::capacity(file_count));
+        for index in 0..file_count {
+            // The offset in the chunk data following the five 32-bit header fields
+            let offset = 20 + (buf.len() - rest.len()) as u64;
+            let name = aux::decode_entry(index, offset, || {
+                let len = rest.read_u16()?;
+                let bytes = aux::read_bytes(&mut rest, usize::from(len))?;
+                Ok(str::from_utf8(&bytes)?.to_string())
+            })?;
+            file_names.push(name);
+        }
+        Ok(LineChunk {
+            version,
+            flags,
+            instruction_count,
+            items,
+            file_names,
+        })
+    }
+    fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        let line_count = self
+            .items
+            .iter()
+            .filter(|i| matches!(i, parts::LineItem::Line(_)))
+            .count();
+        writer.write_u32(self.version)?;
+        writer.write_u32(self.flags)?;
+        writer.write_u32(self.instruction_count)?;
+        writer.write_u32(aux::to_u32("line count", line_count)?)?;
+        writer.write_u32(aux::to_u32("file count", self.file_names.len())?)?;
+
+        let mut buf = Vec::new();
+        for item in &self.items {
+            let operand = match *item {
+                parts::LineItem::File(index) => code::Operand::Atom(index),
+                parts::LineItem::Line(line) => code::Operand::Integer(i64::from(line)),
+            };
+            code::encode_operand(&operand, &mut buf);
+        }
+        writer.write_all(&buf)?;
+        for name in &self.file_names {
+            let len = u16::try_from(name.len()).map_err(|_| Error::TooLarge {
+                what: "file name",
+                size: name.len() as u64,
+            })?;
+            writer.write_u16(len)?;
+            writer.write_all(name.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+impl TypedChunk for LineChunk {
+    const IDS: &'static [Id] = &[*b"Line"];
+}
+
 /// A representation of commonly used chunk.
 ///
 /// ```
@@ -616,6 +974,7 @@ impl Chunk for DocsChunk {
 /// assert_eq!(b"Atom", beam.chunks.iter().nth(0).map(|c| c.id()).unwrap());
 /// ```
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StandardChunk {
     Atom(AtomChunk),
     Code(CodeChunk),
@@ -653,26 +1012,62 @@ impl Chunk for StandardChunk {
         }
     }
     fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
     where
         Self: Sized,
     {
         use self::StandardChunk::*;
         match id {
-            b"Atom" => Ok(Atom(AtomChunk::decode_data(id, reader)?)),
-            b"AtU8" => Ok(Atom(AtomChunk::decode_data(id, reader)?)),
-            b"Code" => Ok(Code(CodeChunk::decode_data(id, reader)?)),
-            b"StrT" => Ok(StrT(StrTChunk::decode_data(id, reader)?)),
-            b"ImpT" => Ok(ImpT(ImpTChunk::decode_data(id, reader)?)),
-            b"ExpT" => Ok(ExpT(ExpTChunk::decode_data(id, reader)?)),
-            b"LitT" => Ok(LitT(LitTChunk::decode_data(id, reader)?)),
-            b"LocT" => Ok(LocT(LocTChunk::decode_data(id, reader)?)),
-            b"FunT" => Ok(FunT(FunTChunk::decode_data(id, reader)?)),
-            b"Attr" => Ok(Attr(AttrChunk::decode_data(id, reader)?)),
-            b"CInf" => Ok(CInf(CInfChunk::decode_data(id, reader)?)),
-            b"Abst" => Ok(Abst(AbstChunk::decode_data(id, reader)?)),
-            b"Dbgi" => Ok(Dbgi(DbgiChunk::decode_data(id, reader)?)),
-            b"Docs" => Ok(Docs(DocsChunk::decode_data(id, reader)?)),
-            _ => Ok(Unknown(RawChunk::decode_data(id, reader)?)),
+            b"Atom" => Ok(Atom(AtomChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"AtU8" => Ok(Atom(AtomChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"Code" => Ok(Code(CodeChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"StrT" => Ok(StrT(StrTChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"ImpT" => Ok(ImpT(ImpTChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"ExpT" => Ok(ExpT(ExpTChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"LitT" => Ok(LitT(LitTChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"LocT" => Ok(LocT(LocTChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"FunT" => Ok(FunT(FunTChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"Attr" => Ok(Attr(AttrChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"CInf" => Ok(CInf(CInfChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"Abst" => Ok(Abst(AbstChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"Dbgi" => Ok(Dbgi(DbgiChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            b"Docs" => Ok(Docs(DocsChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
+            _ => Ok(Unknown(RawChunk::decode_data_with_limits(
+                id, reader, limits,
+            )?)),
         }
     }
     fn encode_data<W: Write>(&self, writer: W) -> Result<()> {
@@ -696,12 +1091,423 @@ impl Chunk for StandardChunk {
     }
 }
 
-mod aux {
+/// A chunk set composed of a base chunk set `C` and a user-defined typed chunk `U`.
+///
+/// Chunks stored under one of `U::IDS` are decoded as `U`, and the others as `C`.
+/// Several user-defined chunks can be combined by nesting
+/// (e.g., `ExtendedChunk<ExtendedChunk<StandardChunk, A>, B>`).
+///
+/// `BeamFile::user_chunk`, `BeamFile::user_chunk_mut` and `BeamFile::insert_or_replace_user`
+/// give typed access to `U`, while `BeamFile::chunk` and the like give access to the typed chunks of `C`.
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use beam_file::{BeamFile, Result};
+/// use beam_file::chunk::{Chunk, ExtendedChunk, Id, StandardChunk, TypedChunk};
+///
+/// struct LineChunk(Vec<u8>);
+/// impl Chunk for LineChunk {
+///     fn id(&self) -> &Id {
+///         b"Line"
+///     }
+///     fn decode_data<R: Read>(_id: &Id, mut reader: R) -> Result<Self> {
+///         let mut buf = Vec::new();
+///         reader.read_to_end(&mut buf)?;
+///         Ok(LineChunk(buf))
+///     }
+///     fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
+///         writer.write_all(&self.0)?;
+///         Ok(())
+///     }
+/// }
+/// impl TypedChunk for LineChunk {
+///     const IDS: &'static [Id] = &[*b"Line"];
+/// }
+///
+/// type MyChunk = ExtendedChunk<StandardChunk, LineChunk>;
+/// let beam = BeamFile::<MyChunk>::from_file("tests/testdata/test.beam").unwrap();
+/// assert!(beam.user_chunk().is_some());
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExtendedChunk<C, U> {
+    /// A chunk of the base chunk set (i.e., not stored under `U::IDS`).
+    Base(C),
+
+    /// A user-defined chunk.
+    User(U),
+}
+impl<C, U> ExtendedChunk<C, U> {
+    /// Returns the base chunk if `self` holds it.
+    pub fn as_base(&self) -> Option<&C> {
+        if let ExtendedChunk::Base(ref c) = *self {
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the base chunk if `self` holds it.
+    pub fn as_base_mut(&mut self) -> Option<&mut C> {
+        if let ExtendedChunk::Base(ref mut c) = *self {
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the user-defined chunk if `self` holds it.
+    pub fn as_user(&self) -> Option<&U> {
+        if let ExtendedChunk::User(ref c) = *self {
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the user-defined chunk if `self` holds it.
+    pub fn as_user_mut(&mut self) -> Option<&mut U> {
+        if let ExtendedChunk::User(ref mut c) = *self {
+            Some(c)
+        } else {
+            None
+        }
+    }
+}
+impl<C: Chunk, U: TypedChunk> Chunk for ExtendedChunk<C, U> {
+    fn id(&self) -> &Id {
+        match *self {
+            ExtendedChunk::Base(ref c) => c.id(),
+            ExtendedChunk::User(ref c) => c.id(),
+        }
+    }
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if U::IDS.contains(id) {
+            Ok(ExtendedChunk::User(U::decode_data_with_limits(
+                id, reader, limits,
+            )?))
+        } else {
+            Ok(ExtendedChunk::Base(C::decode_data_with_limits(
+                id, reader, limits,
+            )?))
+        }
+    }
+    fn encode_data<W: Write>(&self, writer: W) -> Result<()> {
+        match *self {
+            ExtendedChunk::Base(ref c) => c.encode_data(writer),
+            ExtendedChunk::User(ref c) => c.encode_data(writer),
+        }
+    }
+}
+impl<T: TypedChunk, C: AsChunk<T>, U> AsChunk<T> for ExtendedChunk<C, U> {
+    fn as_chunk(&self) -> Option<&T> {
+        self.as_base().and_then(|c| c.as_chunk())
+    }
+    fn as_chunk_mut(&mut self) -> Option<&mut T> {
+        self.as_base_mut().and_then(|c| c.as_chunk_mut())
+    }
+    fn from_chunk(chunk: T) -> Self {
+        ExtendedChunk::Base(C::from_chunk(chunk))
+    }
+}
+
+/// A chunk which falls back to its raw representation if it cannot be decoded as `C`.
+///
+/// ```
+/// use beam_file::LenientBeamFile;
+/// use beam_file::chunk::{AtomChunk, LenientChunk};
+///
+/// let beam = LenientBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// assert!(beam.chunks.iter().all(|c| c.error().is_none()));
+/// assert_eq!("test", beam.chunk::<AtomChunk>().unwrap().atoms[0].name);
+/// ```
+#[derive(Debug)]
+pub enum LenientChunk<C> {
+    /// A successfully decoded chunk.
+    Decoded(C),
+
+    /// A chunk which failed to be decoded as `C`.
+    Malformed {
+        /// The raw representation of the chunk.
+        chunk: RawChunk,
+
+        /// The error occurred while decoding the chunk.
+        error: Error,
+    },
+}
+impl<C> LenientChunk<C> {
+    /// Returns the decoded chunk if decoding succeeded.
+    pub fn decoded(&self) -> Option<&C> {
+        if let LenientChunk::Decoded(ref c) = *self {
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the decoding error if decoding failed.
+    pub fn error(&self) -> Option<&Error> {
+        if let LenientChunk::Malformed { ref error, .. } = *self {
+            Some(error)
+        } else {
+            None
+        }
+    }
+}
+impl<C: Chunk> LenientChunk<C> {
+    /// Decodes `chunk` as `C`, keeping it raw if that fails.
+    pub fn from_raw(chunk: RawChunk) -> Self {
+        Self::from_raw_with_limits(chunk, &DecodeLimits::default())
+    }
+
+    /// Decodes `chunk` as `C` within `limits`, keeping it raw if that fails.
+    pub fn from_raw_with_limits(chunk: RawChunk, limits: &DecodeLimits) -> Self {
+        match C::decode_data_with_limits(&chunk.id, &chunk.data[..], limits) {
+            Ok(c) => LenientChunk::Decoded(c),
+            Err(error) => LenientChunk::Malformed { chunk, error },
+        }
+    }
+}
+impl<C: Chunk> Chunk for LenientChunk<C> {
+    fn id(&self) -> &Id {
+        match *self {
+            LenientChunk::Decoded(ref c) => c.id(),
+            LenientChunk::Malformed { ref chunk, .. } => chunk.id(),
+        }
+    }
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(id: &Id, reader: R, limits: &DecodeLimits) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let chunk = RawChunk::decode_data(id, reader)?;
+        Ok(Self::from_raw_with_limits(chunk, limits))
+    }
+    fn encode_data<W: Write>(&self, writer: W) -> Result<()> {
+        match *self {
+            LenientChunk::Decoded(ref c) => c.encode_data(writer),
+            LenientChunk::Malformed { ref chunk, .. } => chunk.encode_data(writer),
+        }
+    }
+}
+impl<T: TypedChunk, C: AsChunk<T>> AsChunk<T> for LenientChunk<C> {
+    fn as_chunk(&self) -> Option<&T> {
+        self.decoded().and_then(|c| c.as_chunk())
+    }
+    fn as_chunk_mut(&mut self) -> Option<&mut T> {
+        if let LenientChunk::Decoded(ref mut c) = *self {
+            c.as_chunk_mut()
+        } else {
+            None
+        }
+    }
+    fn from_chunk(chunk: T) -> Self {
+        LenientChunk::Decoded(C::from_chunk(chunk))
+    }
+}
+
+/// A chunk which keeps its original bytes to re-encode them as they were.
+///
+/// The original data and padding are written back until the chunk is accessed mutably.
+///
+/// ```
+/// use beam_file::LosslessBeamFile;
+/// use beam_file::chunk::{Chunk, StandardChunk};
+///
+/// let original = std::fs::read("tests/testdata/test.beam").unwrap();
+/// let beam = LosslessBeamFile::<StandardChunk>::from_reader(&original[..]).unwrap();
+/// assert!(beam.beam.chunks.iter().all(|c| !c.is_modified()));
+///
+/// let mut encoded = Vec::new();
+/// beam.to_writer(&mut encoded).unwrap();
+/// assert_eq!(original, encoded);
+/// ```
+#[derive(Debug)]
+pub struct LosslessChunk<C> {
+    chunk: C,
+    original: Option<OriginalBytes>,
+}
+#[derive(Debug)]
+struct OriginalBytes {
+    data: Vec<u8>,
+    padding: Vec<u8>,
+}
+impl<C> LosslessChunk<C> {
+    /// Makes a `LosslessChunk` which has no original bytes.
+    pub fn new(chunk: C) -> Self {
+        LosslessChunk {
+            chunk,
+            original: None,
+        }
+    }
+
+    /// Returns a reference to the chunk.
+    pub fn get(&self) -> &C {
+        &self.chunk
+    }
+
+    /// Returns a mutable reference to the chunk.
+    ///
+    /// The original bytes are discarded, so the chunk will be re-encoded.
+    pub fn get_mut(&mut self) -> &mut C {
+        self.original = None;
+        &mut self.chunk
+    }
+
+    /// Takes the chunk out.
+    pub fn into_inner(self) -> C {
+        self.chunk
+    }
+
+    /// Returns `true` if the chunk will be re-encoded rather than written back as it was.
+    pub fn is_modified(&self) -> bool {
+        self.original.is_none()
+    }
+}
+impl<C: Chunk> Chunk for LosslessChunk<C> {
+    fn id(&self) -> &Id {
+        self.chunk.id()
+    }
+    fn decode_with_limits<R: Read>(mut reader: R, limits: &DecodeLimits) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let header = aux::Header::decode(&mut reader)?;
+        limits.check(Limit::ChunkSize, u64::from(header.data_size))?;
+        let data = aux::read_bytes(&mut reader, header.data_size as usize)?;
+        let padding = aux::read_bytes(&mut reader, aux::padding_size(header.data_size) as usize)?;
+
+        let chunk = C::decode_data_with_limits(&header.chunk_id, &data[..], limits)?;
+        Ok(LosslessChunk {
+            chunk,
+            original: Some(OriginalBytes { data, padding }),
+        })
+    }
+    fn decode_data<R: Read>(id: &Id, reader: R) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_data_with_limits(id, reader, &DecodeLimits::default())
+    }
+    fn decode_data_with_limits<R: Read>(
+        id: &Id,
+        mut reader: R,
+        limits: &DecodeLimits,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let chunk = C::decode_data_with_limits(id, &data[..], limits)?;
+        let padding = vec![0; aux::padding_size(aux::to_u32("chunk size", data.len())?) as usize];
+        Ok(LosslessChunk {
+            chunk,
+            original: Some(OriginalBytes { data, padding }),
+        })
+    }
+    fn encode<W: Write>(&self, mut writer: W) -> Result<()> {
+        match self.original {
+            Some(ref original) => {
+                let data_size = aux::to_u32("chunk size", original.data.len())?;
+                aux::Header::new(self.id(), data_size).encode(&mut writer)?;
+                writer.write_all(&original.data)?;
+                writer.write_all(&original.padding)?;
+                Ok(())
+            }
+            None => self.chunk.encode(writer),
+        }
+    }
+    fn encode_seekable<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        if self.original.is_some() {
+            self.encode(writer)
+        } else {
+            self.chunk.encode_seekable(writer)
+        }
+    }
+    fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        match self.original {
+            Some(ref original) => {
+                writer.write_all(&original.data)?;
+                Ok(())
+            }
+            None => self.chunk.encode_data(writer),
+        }
+    }
+}
+impl<T: TypedChunk, C: AsChunk<T>> AsChunk<T> for LosslessChunk<C> {
+    fn as_chunk(&self) -> Option<&T> {
+        self.chunk.as_chunk()
+    }
+    fn as_chunk_mut(&mut self) -> Option<&mut T> {
+        if self.chunk.as_chunk().is_some() {
+            self.get_mut().as_chunk_mut()
+        } else {
+            None
+        }
+    }
+    fn from_chunk(chunk: T) -> Self {
+        LosslessChunk::new(C::from_chunk(chunk))
+    }
+}
+
+macro_rules! impl_typed_chunk {
+    ($chunk:ident, $variant:ident, [$($id:expr),*]) => {
+        impl TypedChunk for $chunk {
+            const IDS: &'static [Id] = &[$(*$id),*];
+        }
+        impl AsChunk<$chunk> for StandardChunk {
+            fn as_chunk(&self) -> Option<&$chunk> {
+                if let StandardChunk::$variant(ref c) = *self {
+                    Some(c)
+                } else {
+                    None
+                }
+            }
+            fn as_chunk_mut(&mut self) -> Option<&mut $chunk> {
+                if let StandardChunk::$variant(ref mut c) = *self {
+                    Some(c)
+                } else {
+                    None
+                }
+            }
+            fn from_chunk(chunk: $chunk) -> Self {
+                StandardChunk::$variant(chunk)
+            }
+        }
+    };
+}
+impl_typed_chunk!(AtomChunk, Atom, [b"Atom", b"AtU8"]);
+impl_typed_chunk!(CodeChunk, Code, [b"Code"]);
+impl_typed_chunk!(StrTChunk, StrT, [b"StrT"]);
+impl_typed_chunk!(ImpTChunk, ImpT, [b"ImpT"]);
+impl_typed_chunk!(ExpTChunk, ExpT, [b"ExpT"]);
+impl_typed_chunk!(LitTChunk, LitT, [b"LitT"]);
+impl_typed_chunk!(LocTChunk, LocT, [b"LocT"]);
+impl_typed_chunk!(FunTChunk, FunT, [b"FunT"]);
+impl_typed_chunk!(AttrChunk, Attr, [b"Attr"]);
+impl_typed_chunk!(CInfChunk, CInf, [b"CInf"]);
+impl_typed_chunk!(AbstChunk, Abst, [b"Abst"]);
+impl_typed_chunk!(DbgiChunk, Dbgi, [b"Dbgi"]);
+impl_typed_chunk!(DocsChunk, Docs, [b"Docs"]);
+
+pub(crate) mod aux {
     use super::*;
-    use byteorder::BigEndian;
-    use byteorder::ReadBytesExt;
-    use byteorder::WriteBytesExt;
-    use std::io;
+    use crate::io;
 
     pub struct Header {
         pub chunk_id: Id,
@@ -717,16 +1523,78 @@ mod aux {
         pub fn decode<R: io::Read>(mut reader: R) -> io::Result<Self> {
             let mut id = [0; 4];
             reader.read_exact(&mut id)?;
-            let size = reader.read_u32::<BigEndian>()?;
+            let size = reader.read_u32()?;
             Ok(Header::new(&id, size))
         }
         pub fn encode<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
             writer.write_all(&self.chunk_id)?;
-            writer.write_u32::<BigEndian>(self.data_size)?;
+            writer.write_u32(self.data_size)?;
             Ok(())
         }
     }
 
+    /// The maximum number of entries allocated in advance of reading them.
+    const MAX_PREALLOCATED_ENTRIES: usize = 1024;
+
+    pub fn capacity(count: usize) -> usize {
+        count.min(MAX_PREALLOCATED_ENTRIES)
+    }
+
+    pub fn read_count<R: io::Read>(mut reader: R, limits: &DecodeLimits) -> crate::Result<usize> {
+        let count = reader.read_u32()?;
+        limits.check(Limit::Entries, u64::from(count))?;
+        Ok(count as usize)
+    }
+
+    /// Reads exactly `size` bytes without trusting `size` for allocation.
+    pub fn read_bytes<R: io::Read>(reader: R, size: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        reader.take(size as u64).read_to_end(&mut buf)?;
+        if buf.len() != size {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(buf)
+    }
+
+    /// Decodes the `index`-th entry of a table starting at `offset`,
+    /// attaching both to the error.
+    pub fn decode_entry<T, F>(index: usize, offset: u64, f: F) -> crate::Result<T>
+    where
+        F: FnOnce() -> crate::Result<T>,
+    {
+        f().map_err(|e| crate::Error::InEntry {
+            index,
+            offset,
+            source: Box::new(e),
+        })
+    }
+
+    /// A reader that keeps track of the number of bytes read so far.
+    pub struct CountingReader<R> {
+        inner: R,
+        pub position: u64,
+    }
+    impl<R: io::Read> CountingReader<R> {
+        pub fn new(inner: R) -> Self {
+            CountingReader { inner, position: 0 }
+        }
+    }
+    impl<R: io::Read> io::Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let size = self.inner.read(buf)?;
+            self.position += size as u64;
+            Ok(size)
+        }
+    }
+
+    /// Converts `size` to `u32`, failing if it does not fit.
+    pub fn to_u32(what: &'static str, size: usize) -> crate::Result<u32> {
+        u32::try_from(size).map_err(|_| crate::Error::TooLarge {
+            what,
+            size: size as u64,
+        })
+    }
+
     pub fn padding_size(data_size: u32) -> u32 {
         (4 - data_size % 4) % 4
     }
@@ -0,0 +1,814 @@
+//! Instructions in the `"Code"` chunk.
+//!
+//! The instructions are the generic ones listed in `genop.tab` of Erlang/OTP,
+//! and their operands are encoded in the compact term format.
+//!
+//! # Reference
+//! - [`genop.tab`](https://github.com/erlang/otp/blob/master/lib/compiler/src/genop.tab)
+//! - [`beam_asm`](https://github.com/erlang/otp/blob/master/lib/compiler/src/beam_asm.erl)
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::chunk::{AtomChunk, Chunk, CodeChunk, ExpTChunk, FunTChunk, LocTChunk};
+use crate::parts::AtomId;
+use crate::{BeamFile, Error, Result};
+
+const TAG_U: u8 = 0;
+const TAG_I: u8 = 1;
+const TAG_A: u8 = 2;
+const TAG_X: u8 = 3;
+const TAG_Y: u8 = 4;
+const TAG_F: u8 = 5;
+const TAG_H: u8 = 6;
+const TAG_Z: u8 = 7;
+
+const EXT_LIST: u64 = 1;
+const EXT_FLOAT_REGISTER: u64 = 2;
+const EXT_ALLOC_LIST: u64 = 3;
+const EXT_LITERAL: u64 = 4;
+const EXT_TYPED_REGISTER: u64 = 5;
+
+/// The names and arities of the generic instructions, where the opcode is the index plus one.
+///
+/// The names starting with `-` are obsolete instructions which the compiler no longer emits.
+const GENOPS: &[(&str, usize)] = &[
+    ("label", 1),
+    ("func_info", 3),
+    ("int_code_end", 0),
+    ("call", 2),
+    ("call_last", 3),
+    ("call_only", 2),
+    ("call_ext", 2),
+    ("call_ext_last", 3),
+    ("bif0", 2),
+    ("bif1", 4),
+    ("bif2", 5),
+    ("allocate", 2),
+    ("allocate_heap", 3),
+    ("allocate_zero", 2),
+    ("allocate_heap_zero", 3),
+    ("test_heap", 2),
+    ("init", 1),
+    ("deallocate", 1),
+    ("return", 0),
+    ("send", 0),
+    ("remove_message", 0),
+    ("timeout", 0),
+    ("loop_rec", 2),
+    ("loop_rec_end", 1),
+    ("wait", 1),
+    ("wait_timeout", 2),
+    ("-m_plus", 4),
+    ("-m_minus", 4),
+    ("-m_times", 4),
+    ("-m_div", 4),
+    ("-int_div", 4),
+    ("-int_rem", 4),
+    ("-int_band", 4),
+    ("-int_bor", 4),
+    ("-int_bxor", 4),
+    ("-int_bsl", 4),
+    ("-int_bsr", 4),
+    ("-int_bnot", 3),
+    ("is_lt", 3),
+    ("is_ge", 3),
+    ("is_eq", 3),
+    ("is_ne", 3),
+    ("is_eq_exact", 3),
+    ("is_ne_exact", 3),
+    ("is_integer", 2),
+    ("is_float", 2),
+    ("is_number", 2),
+    ("is_atom", 2),
+    ("is_pid", 2),
+    ("is_reference", 2),
+    ("is_port", 2),
+    ("is_nil", 2),
+    ("is_binary", 2),
+    ("-is_constant", 2),
+    ("is_list", 2),
+    ("is_nonempty_list", 2),
+    ("is_tuple", 2),
+    ("test_arity", 3),
+    ("select_val", 3),
+    ("select_tuple_arity", 3),
+    ("jump", 1),
+    ("catch", 2),
+    ("catch_end", 1),
+    ("move", 2),
+    ("get_list", 3),
+    ("get_tuple_element", 3),
+    ("set_tuple_element", 3),
+    ("-put_string", 3),
+    ("put_list", 3),
+    ("-put_tuple", 2),
+    ("-put", 1),
+    ("badmatch", 1),
+    ("if_end", 0),
+    ("case_end", 1),
+    ("call_fun", 1),
+    ("-make_fun", 3),
+    ("is_function", 2),
+    ("call_ext_only", 2),
+    ("-bs_start_match", 2),
+    ("-bs_get_integer", 5),
+    ("-bs_get_float", 5),
+    ("-bs_get_binary", 5),
+    ("-bs_skip_bits", 4),
+    ("-bs_test_tail", 2),
+    ("-bs_save", 1),
+    ("-bs_restore", 1),
+    ("-bs_init", 2),
+    ("-bs_final", 2),
+    ("bs_put_integer", 5),
+    ("bs_put_binary", 5),
+    ("bs_put_float", 5),
+    ("bs_put_string", 2),
+    ("-bs_need_buf", 1),
+    ("fclearerror", 0),
+    ("fcheckerror", 1),
+    ("fmove", 2),
+    ("fconv", 2),
+    ("fadd", 4),
+    ("fsub", 4),
+    ("fmul", 4),
+    ("fdiv", 4),
+    ("fnegate", 3),
+    ("make_fun2", 1),
+    ("try", 2),
+    ("try_end", 1),
+    ("try_case", 1),
+    ("try_case_end", 1),
+    ("raise", 2),
+    ("bs_init2", 6),
+    ("-bs_bits_to_bytes", 3),
+    ("bs_add", 5),
+    ("apply", 1),
+    ("apply_last", 2),
+    ("is_boolean", 2),
+    ("is_function2", 3),
+    ("bs_start_match2", 5),
+    ("bs_get_integer2", 7),
+    ("bs_get_float2", 7),
+    ("bs_get_binary2", 7),
+    ("bs_skip_bits2", 5),
+    ("bs_test_tail2", 3),
+    ("bs_save2", 2),
+    ("bs_restore2", 2),
+    ("gc_bif1", 5),
+    ("gc_bif2", 6),
+    ("-bs_final2", 2),
+    ("-bs_bits_to_bytes2", 2),
+    ("-put_literal", 2),
+    ("is_bitstr", 2),
+    ("bs_context_to_binary", 1),
+    ("bs_test_unit", 3),
+    ("bs_match_string", 4),
+    ("bs_init_writable", 0),
+    ("bs_append", 8),
+    ("bs_private_append", 6),
+    ("trim", 2),
+    ("bs_init_bits", 6),
+    ("bs_get_utf8", 5),
+    ("bs_skip_utf8", 4),
+    ("bs_get_utf16", 5),
+    ("bs_skip_utf16", 4),
+    ("bs_get_utf32", 5),
+    ("bs_skip_utf32", 4),
+    ("bs_utf8_size", 3),
+    ("bs_put_utf8", 3),
+    ("bs_utf16_size", 3),
+    ("bs_put_utf16", 3),
+    ("bs_put_utf32", 3),
+    ("on_load", 0),
+    ("recv_mark", 1),
+    ("recv_set", 1),
+    ("gc_bif3", 7),
+    ("line", 1),
+    ("put_map_assoc", 5),
+    ("put_map_exact", 5),
+    ("is_map", 2),
+    ("has_map_fields", 3),
+    ("get_map_elements", 3),
+    ("is_tagged_tuple", 4),
+    ("build_stacktrace", 0),
+    ("raw_raise", 0),
+    ("get_hd", 2),
+    ("get_tl", 2),
+    ("put_tuple2", 2),
+    ("bs_get_tail", 3),
+    ("bs_start_match3", 4),
+    ("bs_get_position", 3),
+    ("bs_set_position", 2),
+    ("swap", 2),
+    ("bs_start_match4", 4),
+    ("make_fun3", 3),
+    ("init_yregs", 1),
+    ("recv_marker_bind", 2),
+    ("recv_marker_clear", 1),
+    ("recv_marker_reserve", 1),
+    ("recv_marker_use", 1),
+    ("bs_create_bin", 6),
+    ("call_fun2", 3),
+    ("nif_start", 0),
+    ("badrecord", 1),
+    ("update_record", 5),
+    ("bs_match", 3),
+    ("executable_line", 2),
+    ("debug_line", 4),
+];
+
+/// The opcode of a generic instruction.
+pub type Opcode = u8;
+
+/// Returns the name of the instruction `opcode` (without the `-` prefix for obsolete ones).
+pub fn opcode_name(opcode: Opcode) -> Option<&'static str> {
+    genop(opcode).map(|(name, _)| name.trim_start_matches('-'))
+}
+
+/// Returns the number of the operands of the instruction `opcode`.
+pub fn opcode_arity(opcode: Opcode) -> Option<usize> {
+    genop(opcode).map(|(_, arity)| arity)
+}
+
+/// Returns the opcode of the instruction named `name`.
+pub fn opcode(name: &str) -> Option<Opcode> {
+    GENOPS
+        .iter()
+        .position(|(n, _)| n.trim_start_matches('-') == name)
+        .map(|i| (i + 1) as Opcode)
+}
+
+fn genop(opcode: Opcode) -> Option<(&'static str, usize)> {
+    GENOPS.get(usize::from(opcode).checked_sub(1)?).copied()
+}
+
+/// An instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub args: Vec<Operand>,
+}
+impl Instruction {
+    /// Makes an instruction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a generic instruction (see `try_new`).
+    pub fn new(name: &str, args: Vec<Operand>) -> Self {
+        Self::try_new(name, args).unwrap_or_else(|| panic!("unknown instruction: {}", name))
+    }
+
+    /// Makes an instruction, or returns `None` if `name` is not a generic instruction.
+    ///
+    /// ```
+    /// use beam_file::code::{Instruction, Operand};
+    ///
+    /// assert!(Instruction::try_new("return", Vec::new()).is_some());
+    /// assert!(Instruction::try_new("no_such_instruction", vec![Operand::X(0)]).is_none());
+    /// ```
+    pub fn try_new(name: &str, args: Vec<Operand>) -> Option<Self> {
+        let opcode = opcode(name)?;
+        Some(Instruction { opcode, args })
+    }
+
+    /// Returns the name of the instruction (e.g., `"move"`).
+    pub fn name(&self) -> &'static str {
+        opcode_name(self.opcode).unwrap_or("unknown")
+    }
+}
+
+/// An operand of an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    /// An untagged unsigned integer (e.g., an arity or an index into a table).
+    Unsigned(u64),
+
+    Integer(i64),
+
+    /// An integer which does not fit in `i64`.
+    BigInteger {
+        negative: bool,
+
+        /// The absolute value in little-endian order.
+        magnitude: Vec<u8>,
+    },
+
+    /// An atom, where `0` is `[]` (i.e., `nil`).
+    Atom(AtomId),
+
+    X(u32),
+    Y(u32),
+    Label(u32),
+    Character(u32),
+    List(Vec<Operand>),
+    FloatRegister(u32),
+
+    /// The amount of heap to allocate as pairs of a kind (`0` for words, `1` for floats and `2` for funs)
+    /// and a number.
+    AllocList(Vec<(u32, u32)>),
+
+    /// An index into the `"LitT"` chunk.
+    Literal(u32),
+
+    /// A register annotated with an index into the `"Type"` chunk.
+    TypedRegister(Box<Operand>, u32),
+}
+
+/// Decodes the instructions in `bytecode` (i.e., `CodeChunk::bytecode`).
+///
+/// The decoding stops after `int_code_end`.
+///
+/// ```
+/// use beam_file::StandardBeamFile;
+/// use beam_file::chunk::CodeChunk;
+/// use beam_file::code::{self, Operand};
+///
+/// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let code = beam.chunk::<CodeChunk>().unwrap();
+/// let instructions = code::decode(&code.bytecode).unwrap();
+/// assert_eq!("label", instructions[0].name());
+/// assert_eq!(vec![Operand::Unsigned(1)], instructions[0].args);
+/// assert_eq!(code.bytecode, code::encode(&instructions).unwrap());
+/// ```
+pub fn decode(bytecode: &[u8]) -> Result<Vec<Instruction>> {
+    decode_with_offsets(bytecode).map(|(instructions, _)| instructions)
+}
+
+/// Same as `decode` except that the byte offsets of the instructions are also returned.
+pub(crate) fn decode_with_offsets(bytecode: &[u8]) -> Result<(Vec<Instruction>, Vec<usize>)> {
+    let mut reader = CompactReader::new(bytecode);
+    let mut instructions = Vec::new();
+    let mut offsets = Vec::new();
+    while !reader.is_empty() {
+        let offset = reader.position;
+        let opcode = reader.u8()?;
+        let arity = opcode_arity(opcode).ok_or(Error::UnknownOpcode { offset, opcode })?;
+        let args = (0..arity)
+            .map(|_| reader.operand())
+            .collect::<Result<Vec<_>>>()?;
+        instructions.push(Instruction { opcode, args });
+        offsets.push(offset);
+        if opcode_name(opcode) == Some("int_code_end") {
+            break;
+        }
+    }
+    Ok((instructions, offsets))
+}
+
+/// Encodes `instructions` in the same way as `beam_asm`.
+pub fn encode(instructions: &[Instruction]) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for instruction in instructions {
+        if opcode_arity(instruction.opcode) != Some(instruction.args.len()) {
+            return Err(Error::InvalidInstruction {
+                offset: bytes.len(),
+                reason: "wrong number of operands",
+            });
+        }
+        bytes.push(instruction.opcode);
+        for arg in &instruction.args {
+            encode_operand(arg, &mut bytes);
+        }
+    }
+    Ok(bytes)
+}
+
+/// A function in the `"Code"` chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub arity: u32,
+
+    /// The label of the first instruction after `func_info`.
+    pub entry: u32,
+
+    /// The range of the function in `CodeChunk::bytecode`.
+    pub range: Range<usize>,
+
+    /// The instructions from the label preceding `func_info` to the next function.
+    pub instructions: Vec<Instruction>,
+
+    /// The offsets of the instructions in `CodeChunk::bytecode`.
+    pub offsets: Vec<usize>,
+
+    /// The index in the `"ExpT"` chunk if the function is exported.
+    pub export: Option<usize>,
+
+    /// The index in the `"LocT"` chunk if the function is local.
+    pub local: Option<usize>,
+
+    /// The indices in the `"FunT"` chunk of the funs implemented by the function.
+    pub funs: Vec<usize>,
+}
+
+/// Splits the `"Code"` chunk of `beam` into functions.
+///
+/// A function starts with the label (and `line` instructions) preceding `func_info`,
+/// and ends at the start of the next function or `int_code_end`.
+/// The `"Atom"` (or `"AtU8"`) and `"Code"` chunks are required.
+///
+/// ```
+/// use beam_file::StandardBeamFile;
+/// use beam_file::code;
+///
+/// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let functions = code::functions(&beam).unwrap();
+/// let hello = &functions[0];
+/// assert_eq!(("hello", 1, 2), (hello.name.as_str(), hello.arity, hello.entry));
+/// assert_eq!(0..25, hello.range);
+/// assert_eq!("func_info", hello.instructions[2].name());
+/// assert_eq!(Some(2), hello.export);
+///
+/// let fun = &functions[3];
+/// assert_eq!("-hello/1-fun-0-", fun.name);
+/// assert_eq!((Some(0), vec![0]), (fun.local, fun.funs.clone()));
+/// ```
+pub fn functions<C: Chunk>(beam: &BeamFile<C>) -> Result<Vec<Function>> {
+    let atoms = beam.require_chunk::<AtomChunk>()?;
+    let code = beam.require_chunk::<CodeChunk>()?;
+    let exports = beam.decode_chunk::<ExpTChunk>()?.map(|c| c.exports);
+    let locals = beam.decode_chunk::<LocTChunk>()?.map(|c| c.locals);
+    let funs = beam.decode_chunk::<FunTChunk>()?.map(|c| c.functions);
+
+    let (instructions, mut offsets) = decode_with_offsets(&code.bytecode)?;
+    offsets.push(code.bytecode.len());
+    let mut functions = Vec::new();
+    for range in function_ranges(&instructions) {
+        let (name, arity, entry) = function_header(&instructions[range.clone()]);
+        functions.push(Function {
+            name: String::from(atoms.name(name)?),
+            arity,
+            entry,
+            range: offsets[range.start]..offsets[range.end],
+            instructions: instructions[range.clone()].to_vec(),
+            offsets: offsets[range].to_vec(),
+            export: exports
+                .iter()
+                .flatten()
+                .position(|e| e.function == name && e.arity == arity),
+            local: locals
+                .iter()
+                .flatten()
+                .position(|l| l.function == name && l.arity == arity),
+            funs: funs
+                .iter()
+                .flatten()
+                .enumerate()
+                .filter(|(_, f)| f.label == entry)
+                .map(|(i, _)| i)
+                .collect(),
+        });
+    }
+    Ok(functions)
+}
+
+/// Returns the ranges of the functions in `instructions` (see `functions`).
+pub(crate) fn function_ranges(instructions: &[Instruction]) -> Vec<Range<usize>> {
+    let mut starts = Vec::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        if instruction.name() != "func_info" {
+            continue;
+        }
+        let start = instructions[..i]
+            .iter()
+            .rposition(|i| i.name() != "line")
+            .filter(|&j| instructions[j].name() == "label")
+            .unwrap_or(i);
+        starts.push(start);
+    }
+    let end = instructions
+        .iter()
+        .position(|i| i.name() == "int_code_end")
+        .unwrap_or(instructions.len());
+    let mut ranges = Vec::with_capacity(starts.len());
+    for (k, &start) in starts.iter().enumerate() {
+        ranges.push(start..starts.get(k + 1).copied().unwrap_or(end));
+    }
+    ranges
+}
+
+/// Returns the name, arity and entry label of `function`, which must contain `func_info`.
+pub(crate) fn function_header(function: &[Instruction]) -> (AtomId, u32, u32) {
+    let i = function
+        .iter()
+        .position(|i| i.name() == "func_info")
+        .expect("no func_info");
+    let (name, arity) = match function[i].args[..] {
+        [_, Operand::Atom(name), Operand::Unsigned(arity)] => (name, arity as u32),
+        _ => (0, 0),
+    };
+    let entry = match function.get(i + 1).map(|i| (i.name(), &i.args[..])) {
+        Some(("label", &[Operand::Unsigned(label)])) => label as u32,
+        _ => 0,
+    };
+    (name, arity, entry)
+}
+
+/// Appends the compact term encoding of `operand` to `bytes`.
+pub(crate) fn encode_operand(operand: &Operand, bytes: &mut Vec<u8>) {
+    match *operand {
+        Operand::Unsigned(n) => encode_unsigned(TAG_U, n, bytes),
+        Operand::Integer(n) if n >= 0 => encode_unsigned(TAG_I, n as u64, bytes),
+        Operand::Integer(n) => {
+            // Negative numbers take at least two bytes
+            let be = n.to_be_bytes();
+            let skip = be
+                .windows(2)
+                .take_while(|w| w[0] == 0xFF && w[1] & 0x80 != 0)
+                .count()
+                .min(6);
+            encode_bytes(TAG_I, &be[skip..], bytes);
+        }
+        Operand::BigInteger {
+            negative,
+            ref magnitude,
+        } => encode_bytes(TAG_I, &twos_complement(negative, magnitude), bytes),
+        Operand::Atom(n) => encode_unsigned(TAG_A, u64::from(n), bytes),
+        Operand::X(n) => encode_unsigned(TAG_X, u64::from(n), bytes),
+        Operand::Y(n) => encode_unsigned(TAG_Y, u64::from(n), bytes),
+        Operand::Label(n) => encode_unsigned(TAG_F, u64::from(n), bytes),
+        Operand::Character(n) => encode_unsigned(TAG_H, u64::from(n), bytes),
+        Operand::List(ref elements) => {
+            encode_unsigned(TAG_Z, EXT_LIST, bytes);
+            encode_unsigned(TAG_U, elements.len() as u64, bytes);
+            for e in elements {
+                encode_operand(e, bytes);
+            }
+        }
+        Operand::FloatRegister(n) => {
+            encode_unsigned(TAG_Z, EXT_FLOAT_REGISTER, bytes);
+            encode_unsigned(TAG_U, u64::from(n), bytes);
+        }
+        Operand::AllocList(ref pairs) => {
+            encode_unsigned(TAG_Z, EXT_ALLOC_LIST, bytes);
+            encode_unsigned(TAG_U, pairs.len() as u64, bytes);
+            for &(kind, n) in pairs {
+                encode_unsigned(TAG_U, u64::from(kind), bytes);
+                encode_unsigned(TAG_U, u64::from(n), bytes);
+            }
+        }
+        Operand::Literal(n) => {
+            encode_unsigned(TAG_Z, EXT_LITERAL, bytes);
+            encode_unsigned(TAG_U, u64::from(n), bytes);
+        }
+        Operand::TypedRegister(ref register, n) => {
+            encode_unsigned(TAG_Z, EXT_TYPED_REGISTER, bytes);
+            encode_operand(register, bytes);
+            encode_unsigned(TAG_U, u64::from(n), bytes);
+        }
+    }
+}
+
+fn encode_unsigned(tag: u8, n: u64, bytes: &mut Vec<u8>) {
+    if n < 0x10 {
+        bytes.push(((n as u8) << 4) | tag);
+    } else if n < 0x800 {
+        bytes.push((((n >> 3) as u8) & 0b1110_0000) | tag | 0b0000_1000);
+        bytes.push(n as u8);
+    } else {
+        // A leading zero byte keeps the value positive when read as a signed integer
+        let be = n.to_be_bytes();
+        let skip = be.iter().take_while(|&&b| b == 0).count();
+        let mut value = Vec::with_capacity(9);
+        if be[skip] & 0x80 != 0 {
+            value.push(0);
+        }
+        value.extend_from_slice(&be[skip..]);
+        encode_bytes(tag, &value, bytes);
+    }
+}
+
+fn encode_bytes(tag: u8, value: &[u8], bytes: &mut Vec<u8>) {
+    if value.len() <= 8 {
+        bytes.push((((value.len() - 2) as u8) << 5) | 0b0001_1000 | tag);
+    } else {
+        bytes.push(0b1111_1000 | tag);
+        encode_unsigned(TAG_U, (value.len() - 9) as u64, bytes);
+    }
+    bytes.extend_from_slice(value);
+}
+
+/// Converts a sign and magnitude (little-endian) to the shortest two's complement (big-endian)
+/// which takes at least two bytes.
+fn twos_complement(negative: bool, magnitude: &[u8]) -> Vec<u8> {
+    let mut be = magnitude.iter().rev().copied().collect::<Vec<_>>();
+    be.insert(0, 0);
+    if negative {
+        let mut carry = true;
+        for b in be.iter_mut().rev() {
+            let (v, c) = (!*b).overflowing_add(u8::from(carry));
+            *b = v;
+            carry = c;
+        }
+    }
+    let redundant =
+        |w: &[u8]| (w[0] == 0 && w[1] & 0x80 == 0) || (w[0] == 0xFF && w[1] & 0x80 != 0);
+    let skip = be.windows(2).take_while(|w| redundant(w)).count();
+    let mut value = be.split_off(skip);
+    if value.len() < 2 {
+        let sign = if value[0] & 0x80 != 0 { 0xFF } else { 0 };
+        value.insert(0, sign);
+    }
+    value
+}
+
+/// A reader of the compact term format.
+#[derive(Debug)]
+pub(crate) struct CompactReader<'a> {
+    bytes: &'a [u8],
+    pub position: usize,
+}
+impl<'a> CompactReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        CompactReader { bytes, position: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn error(&self, reason: &'static str) -> Error {
+        Error::InvalidInstruction {
+            offset: self.position,
+            reason,
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        let b = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| self.error("unexpected end of code"))?;
+        self.position += 1;
+        Ok(b)
+    }
+
+    pub fn operand(&mut self) -> Result<Operand> {
+        let b = self.u8()?;
+        let tag = b & 0b111;
+        if tag == TAG_Z {
+            return self.extended(b);
+        }
+        let value = self.value(b)?;
+        let small = || match value {
+            Value::Small(n) => u32::try_from(n).ok(),
+            Value::Large(_) => None,
+        };
+        let operand = match tag {
+            TAG_U => match value {
+                Value::Small(n) => Operand::Unsigned(n),
+                Value::Large(_) => return Err(self.error("too large unsigned integer")),
+            },
+            TAG_I => match value {
+                Value::Small(n) => Operand::Integer(n as i64),
+                Value::Large(be) => signed_integer(&be),
+            },
+            _ => {
+                let n = small().ok_or_else(|| self.error("too large operand"))?;
+                match tag {
+                    TAG_A => Operand::Atom(n),
+                    TAG_X => Operand::X(n),
+                    TAG_Y => Operand::Y(n),
+                    TAG_F => Operand::Label(n),
+                    _ => Operand::Character(n),
+                }
+            }
+        };
+        Ok(operand)
+    }
+
+    pub fn unsigned(&mut self) -> Result<u64> {
+        match self.operand()? {
+            Operand::Unsigned(n) => Ok(n),
+            _ => Err(self.error("expected an unsigned integer")),
+        }
+    }
+
+    fn unsigned_u32(&mut self) -> Result<u32> {
+        let n = self.unsigned()?;
+        u32::try_from(n).map_err(|_| self.error("too large operand"))
+    }
+
+    /// Reads the value following the first byte `b`.
+    fn value(&mut self, b: u8) -> Result<Value> {
+        if b & 0b1000 == 0 {
+            return Ok(Value::Small(u64::from(b >> 4)));
+        }
+        if b & 0b1_0000 == 0 {
+            let low = self.u8()?;
+            return Ok(Value::Small(
+                (u64::from(b & 0b1110_0000) << 3) | u64::from(low),
+            ));
+        }
+        let len = match b >> 5 {
+            7 => {
+                let n = self.unsigned()?;
+                usize::try_from(n)
+                    .ok()
+                    .and_then(|n| n.checked_add(9))
+                    .ok_or_else(|| self.error("too large operand"))?
+            }
+            n => usize::from(n) + 2,
+        };
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| self.error("unexpected end of code"))?;
+        let be = &self.bytes[self.position..end];
+        self.position = end;
+
+        // The value is read as unsigned except for integer operands
+        if b & 0b111 == TAG_I {
+            if len <= 8 {
+                let fill = if be[0] & 0x80 != 0 { 0xFF } else { 0 };
+                let mut buf = [fill; 8];
+                buf[8 - len..].copy_from_slice(be);
+                return Ok(Value::Small(u64::from_be_bytes(buf)));
+            }
+        } else {
+            let be = &be[be.iter().take_while(|&&b| b == 0).count()..];
+            if be.len() <= 8 {
+                let mut buf = [0; 8];
+                buf[8 - be.len()..].copy_from_slice(be);
+                return Ok(Value::Small(u64::from_be_bytes(buf)));
+            }
+        }
+        Ok(Value::Large(be.to_vec()))
+    }
+
+    fn extended(&mut self, b: u8) -> Result<Operand> {
+        let kind = match self.value(b)? {
+            Value::Small(n) => n,
+            Value::Large(_) => return Err(self.error("unknown extended operand")),
+        };
+        let operand = match kind {
+            EXT_LIST => {
+                let len = self.unsigned()?;
+                let mut elements = Vec::new();
+                for _ in 0..len {
+                    elements.push(self.operand()?);
+                }
+                Operand::List(elements)
+            }
+            EXT_FLOAT_REGISTER => Operand::FloatRegister(self.unsigned_u32()?),
+            EXT_ALLOC_LIST => {
+                let len = self.unsigned()?;
+                let mut pairs = Vec::new();
+                for _ in 0..len {
+                    pairs.push((self.unsigned_u32()?, self.unsigned_u32()?));
+                }
+                Operand::AllocList(pairs)
+            }
+            EXT_LITERAL => Operand::Literal(self.unsigned_u32()?),
+            EXT_TYPED_REGISTER => {
+                let register = self.operand()?;
+                if !matches!(register, Operand::X(_) | Operand::Y(_)) {
+                    return Err(self.error("typed operand which is not a register"));
+                }
+                Operand::TypedRegister(Box::new(register), self.unsigned_u32()?)
+            }
+            _ => return Err(self.error("unknown extended operand")),
+        };
+        Ok(operand)
+    }
+}
+
+enum Value {
+    Small(u64),
+    Large(Vec<u8>),
+}
+
+/// Converts a big-endian two's complement integer.
+fn signed_integer(be: &[u8]) -> Operand {
+    let negative = be.first().is_some_and(|&b| b & 0x80 != 0);
+    let mut magnitude = be.iter().rev().copied().collect::<Vec<_>>();
+    if negative {
+        let mut carry = true;
+        for b in magnitude.iter_mut() {
+            let (v, c) = (!*b).overflowing_add(u8::from(carry));
+            *b = v;
+            carry = c;
+        }
+    }
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    if magnitude.len() <= 8 {
+        let mut buf = [0; 8];
+        buf[..magnitude.len()].copy_from_slice(&magnitude);
+        let n = u64::from_le_bytes(buf);
+        if negative && n <= 1 << 63 {
+            return Operand::Integer((n as i64).wrapping_neg());
+        } else if !negative && n < 1 << 63 {
+            return Operand::Integer(n as i64);
+        }
+    }
+    Operand::BigInteger {
+        negative,
+        magnitude,
+    }
+}
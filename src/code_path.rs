@@ -0,0 +1,240 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::chunk::{AtomChunk, StandardChunk};
+use crate::{Error, Result, Scanner};
+
+/// The code path of the Erlang VM.
+///
+/// The directories are searched in order, like the code server does,
+/// so a module is loaded from the first directory containing `<module>.beam`.
+///
+/// ```
+/// use beam_file::CodePath;
+///
+/// let mut code_path = CodePath::new();
+/// code_path.add_dir("tests/testdata");
+/// let index = code_path.index();
+/// let file = index.resolve("test").unwrap();
+/// assert_eq!("test", file.module);
+/// assert!(index.conflicts().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CodePath {
+    dirs: Vec<PathBuf>,
+}
+impl CodePath {
+    /// Makes an empty `CodePath`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `dir` (typically an `ebin` directory) to the end of the code path.
+    ///
+    /// This corresponds to `code:add_pathz/1`.
+    pub fn add_dir<P: AsRef<Path>>(&mut self, dir: P) {
+        self.dirs.push(dir.as_ref().to_path_buf());
+    }
+
+    /// Appends the `ebin` directories of the applications under `root` to the end of the code path.
+    ///
+    /// `root` is a library directory such as an `ERL_LIBS` entry or the `lib/` directory of a release.
+    /// Its subdirectories are named `App` or `App-Vsn`, and only the latest version of each
+    /// application is added, in the order of the application names.
+    pub fn add_lib_root<P: AsRef<Path>>(&mut self, root: P) -> Result<()> {
+        let root = root.as_ref();
+        let apps = read_apps(root).map_err(|e| Error::InFile {
+            path: root.to_path_buf(),
+            source: Box::new(e.into()),
+        })?;
+        for (_, (_, dir)) in apps {
+            let ebin = dir.join("ebin");
+            if ebin.is_dir() {
+                self.dirs.push(ebin);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the directories in the order they are searched.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Reads the module names of the BEAM files in the code path.
+    ///
+    /// Directories which do not exist are skipped, as the code server does.
+    pub fn index(&self) -> CodeIndex {
+        let mut errors = Vec::new();
+        let mut paths = Vec::new();
+        for dir in &self.dirs {
+            match read_beam_files(dir) {
+                Ok(files) => paths.extend(files),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => errors.push((
+                    dir.clone(),
+                    Error::InFile {
+                        path: dir.clone(),
+                        source: Box::new(e.into()),
+                    },
+                )),
+            }
+        }
+
+        let scanner = Scanner {
+            chunk_ids: Some(vec![*b"Atom", *b"AtU8"]),
+            ..Scanner::default()
+        };
+        let mut files = Vec::new();
+        for entry in scanner.scan::<StandardChunk, _>(&paths) {
+            let module = entry.result.and_then(|beam| {
+                beam.chunk::<AtomChunk>()
+                    .and_then(|c| c.atoms.first())
+                    .map(|a| a.name.clone())
+                    .ok_or_else(|| Error::InFile {
+                        path: entry.path.clone(),
+                        source: Box::new(Error::MissingChunk { id: *b"Atom" }),
+                    })
+            });
+            match module {
+                Ok(module) => files.push(ModuleFile {
+                    path: entry.path,
+                    module,
+                }),
+                Err(e) => errors.push((entry.path, e)),
+            }
+        }
+        CodeIndex { files, errors }
+    }
+}
+
+/// The BEAM files found in a `CodePath`.
+#[derive(Debug)]
+pub struct CodeIndex {
+    files: Vec<ModuleFile>,
+
+    /// The files and directories which could not be read.
+    pub errors: Vec<(PathBuf, Error)>,
+}
+impl CodeIndex {
+    /// Returns the file which the code server loads for `module`.
+    ///
+    /// This is the first `<module>.beam` in the code path.
+    /// Note that the VM fails to load it if `ModuleFile::module` is not `module`.
+    pub fn resolve(&self, module: &str) -> Option<&ModuleFile> {
+        self.files.iter().find(|f| f.file_name() == Some(module))
+    }
+
+    /// Returns all files in the order of the code path.
+    pub fn files(&self) -> &[ModuleFile] {
+        &self.files
+    }
+
+    /// Returns the problems which make the loaded module differ from what is expected.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        let mut modules = BTreeMap::<_, Vec<_>>::new();
+        for file in &self.files {
+            if file.file_name() != Some(file.module.as_str()) {
+                conflicts.push(Conflict::NameMismatch {
+                    path: file.path.clone(),
+                    module: file.module.clone(),
+                });
+            }
+            modules.entry(&file.module).or_default().push(file);
+        }
+        for (module, files) in modules {
+            if files.len() > 1 {
+                conflicts.push(Conflict::Duplicate {
+                    module: module.clone(),
+                    paths: files.iter().map(|f| f.path.clone()).collect(),
+                });
+            }
+        }
+        conflicts
+    }
+}
+
+/// A BEAM file in a `CodePath`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleFile {
+    /// The path of the file.
+    pub path: PathBuf,
+
+    /// The module name (i.e., the first atom in the `"Atom"` or `"AtU8"` chunk).
+    pub module: String,
+}
+impl ModuleFile {
+    fn file_name(&self) -> Option<&str> {
+        self.path.file_stem().and_then(|s| s.to_str())
+    }
+}
+
+/// A problem found by `CodeIndex::conflicts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// The file name differs from the module name, so the VM refuses to load it.
+    NameMismatch { path: PathBuf, module: String },
+
+    /// The module is defined in more than one file.
+    ///
+    /// `paths` are in the order of the code path, and
+    /// all but the one returned by `CodeIndex::resolve` are shadowed.
+    Duplicate { module: String, paths: Vec<PathBuf> },
+}
+
+fn read_beam_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "beam") && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the latest version of each application under `root`, keyed by the application name.
+fn read_apps(root: &Path) -> io::Result<BTreeMap<String, (Vec<String>, PathBuf)>> {
+    let mut apps = BTreeMap::<String, (Vec<String>, PathBuf)>::new();
+    for entry in fs::read_dir(root)? {
+        let dir = entry?.path();
+        let Some(name) = dir.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !dir.is_dir() {
+            continue;
+        }
+        let (app, vsn) = match name.rsplit_once('-') {
+            Some((app, vsn)) if vsn.starts_with(|c: char| c.is_ascii_digit()) => (
+                app.to_owned(),
+                vsn.split('.').map(|s| s.to_owned()).collect(),
+            ),
+            _ => (name.to_owned(), Vec::new()),
+        };
+        match apps.get(&app) {
+            Some((latest, _)) if compare_versions(latest, &vsn) != Ordering::Less => {}
+            _ => {
+                apps.insert(app, (vsn, dir));
+            }
+        }
+    }
+    Ok(apps)
+}
+
+fn compare_versions(a: &[String], b: &[String]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let o = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if o != Ordering::Equal {
+            return o;
+        }
+    }
+    a.len().cmp(&b.len())
+}
@@ -0,0 +1,508 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+
+use crate::chunk::{
+    AtomChunk, AttrChunk, Chunk, CodeChunk, ExpTChunk, FunTChunk, ImpTChunk, LineChunk, LitTChunk,
+    StrTChunk,
+};
+use crate::code::{self, Instruction, Operand};
+use crate::io::Write;
+use crate::parts::{AtomId, Location};
+use crate::term::Term;
+use crate::{BeamFile, Error, Result};
+
+/// The instructions rendered as `{test,Name,Fail,Args}` by `erlc -S`.
+const TESTS: &[&str] = &[
+    "is_lt",
+    "is_ge",
+    "is_eq",
+    "is_ne",
+    "is_eq_exact",
+    "is_ne_exact",
+    "is_integer",
+    "is_float",
+    "is_number",
+    "is_atom",
+    "is_pid",
+    "is_reference",
+    "is_port",
+    "is_nil",
+    "is_binary",
+    "is_list",
+    "is_nonempty_list",
+    "is_tuple",
+    "test_arity",
+    "is_function",
+    "is_boolean",
+    "is_function2",
+    "is_bitstr",
+    "is_map",
+    "is_tagged_tuple",
+    "bs_test_tail2",
+    "bs_test_unit",
+];
+
+/// A disassembler which renders the `"Code"` chunk in the same textual form as `erlc -S`.
+///
+/// The operands are resolved using the other chunks
+/// (e.g., `{call_ext,2,{extfunc,io,format,2}}` instead of an index into the `"ImpT"` chunk).
+/// Each term is printed on a single line.
+/// The instructions without a dedicated form in `erlc -S` (e.g., most of the `bs_*` ones)
+/// are printed as a tuple of the name and operands.
+///
+/// ```
+/// use beam_file::{Disassembler, RawBeamFile};
+///
+/// let beam = RawBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let mut disasm = Disassembler::new(&beam).unwrap();
+/// disasm.add_source("test.erl", &std::fs::read_to_string("tests/testdata/test.erl").unwrap());
+///
+/// let mut buf = Vec::new();
+/// disasm.to_writer(&mut buf).unwrap();
+/// let text = String::from_utf8(buf).unwrap();
+/// assert!(text.starts_with("{module, test}.  %% version = 0\n"));
+/// assert!(text.contains("\n{function, hello, 1, 2}.\n  {label,1}.\n"));
+/// assert!(text.contains("\n    {move,{literal,\"Hello ~p!\"},{x,0}}.\n"));
+/// assert!(text.contains("\n%% test.erl:9:     Hello(),\n    {line,[{location,\"test.erl\",9}]}.\n"));
+/// ```
+#[derive(Debug)]
+pub struct Disassembler {
+    atoms: AtomChunk,
+    code: CodeChunk,
+    imports: Option<ImpTChunk>,
+    exports: Option<ExpTChunk>,
+    funs: Option<FunTChunk>,
+    strings: Option<StrTChunk>,
+    literals: Vec<Term>,
+    attributes: Option<Term>,
+    locations: Vec<Option<Location>>,
+    file_names: Vec<String>,
+    sources: BTreeMap<String, Vec<String>>,
+}
+impl Disassembler {
+    /// Makes a disassembler of `beam`, which must have the `"Atom"` (or `"AtU8"`) and `"Code"` chunks.
+    pub fn new<C: Chunk>(beam: &BeamFile<C>) -> Result<Self> {
+        let atoms = beam.require_chunk::<AtomChunk>()?;
+        let literals = match beam.decode_chunk::<LitTChunk>()? {
+            Some(chunk) => chunk
+                .literals
+                .iter()
+                .map(|l| Term::decode(l))
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        let attributes = beam
+            .decode_chunk::<AttrChunk>()?
+            .map(|chunk| Term::decode(&chunk.term))
+            .transpose()?;
+        let (locations, names) = match beam.decode_chunk::<LineChunk>()? {
+            Some(chunk) => (chunk.locations(), chunk.file_names),
+            None => (vec![None], Vec::new()),
+        };
+        // The file index `0` refers to the source of the module itself
+        let mut file_names = vec![format!("{}.erl", atoms.name(1)?)];
+        file_names.extend(names);
+        Ok(Disassembler {
+            code: beam.require_chunk()?,
+            imports: beam.decode_chunk()?,
+            exports: beam.decode_chunk()?,
+            funs: beam.decode_chunk()?,
+            strings: beam.decode_chunk()?,
+            atoms,
+            literals,
+            attributes,
+            locations,
+            file_names,
+            sources: BTreeMap::new(),
+        })
+    }
+
+    /// Adds the source code of `file` to print its lines as comments before the `line` instructions.
+    ///
+    /// `file` is matched against the file names in the `"Line"` chunk.
+    pub fn add_source(&mut self, file: &str, text: &str) {
+        let lines = text.lines().map(String::from).collect();
+        self.sources.insert(String::from(file), lines);
+    }
+
+    /// Writes the disassembly of the module.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+        let (instructions, offsets) = code::decode_with_offsets(&self.code.bytecode)?;
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{{module, {}}}.  %% version = 0\n\n\
+             {{exports, {}}}.\n\n\
+             {{attributes, {}}}.\n\n\
+             {{labels, {}}}.\n",
+            Term::Atom(String::from(self.atoms.name(1)?)),
+            self.exports_term()?,
+            self.attributes.clone().unwrap_or(Term::List(Vec::new())),
+            self.code.label_count
+        );
+        writer.write_all(out.as_bytes())?;
+
+        for range in code::function_ranges(&instructions) {
+            out.clear();
+            let offsets = &offsets[range.clone()];
+            let function = &instructions[range];
+            let (name, arity, entry) = code::function_header(function);
+            let _ = write!(
+                out,
+                "\n\n{{function, {}, {}, {}}}.\n",
+                self.atom(name)?,
+                arity,
+                entry
+            );
+            for (instruction, &offset) in function.iter().zip(offsets) {
+                if let ("line", [Operand::Unsigned(index)]) =
+                    (instruction.name(), &instruction.args[..])
+                {
+                    self.write_source_line(&mut out, *index);
+                }
+                let indent = if instruction.name() == "label" { 2 } else { 4 };
+                let _ = writeln!(
+                    out,
+                    "{:indent$}{}.",
+                    "",
+                    self.instruction(instruction, offset)?,
+                    indent = indent
+                );
+            }
+            writer.write_all(out.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Returns `instruction` as a term in the form of `erlc -S` (e.g., `{move,{x,0},{y,1}}`).
+    ///
+    /// Malformed instructions are reported as `Error::InvalidInstruction` with `offset`,
+    /// which is the byte offset of the instruction in the bytecode (e.g., `Function::offsets`).
+    pub fn instruction(&self, instruction: &Instruction, offset: usize) -> Result<Term> {
+        use Operand::*;
+
+        let name = instruction.name();
+        let args = &instruction.args;
+        let term = match (name, &args[..]) {
+            ("line", [Unsigned(index)]) => tuple("line", vec![self.location(*index, offset)?]),
+            ("call_ext" | "call_ext_only", [arity, Unsigned(import)]) => tuple(
+                name,
+                vec![self.operand(arity, offset)?, self.extfunc(*import, offset)?],
+            ),
+            ("call_ext_last", [arity, Unsigned(import), dealloc]) => tuple(
+                name,
+                vec![
+                    self.operand(arity, offset)?,
+                    self.extfunc(*import, offset)?,
+                    self.operand(dealloc, offset)?,
+                ],
+            ),
+            ("bif0", [Unsigned(bif), dst]) => tuple(
+                "bif",
+                vec![
+                    self.bif_name(*bif, offset)?,
+                    tuple("f", vec![Term::Integer(0)]),
+                    Term::List(Vec::new()),
+                    self.operand(dst, offset)?,
+                ],
+            ),
+            ("bif1" | "bif2", [fail, Unsigned(bif), rest @ ..]) => {
+                let (dst, bif_args) = rest.split_last().ok_or(Error::InvalidInstruction {
+                    offset,
+                    reason: "missing destination",
+                })?;
+                tuple(
+                    "bif",
+                    vec![
+                        self.bif_name(*bif, offset)?,
+                        self.operand(fail, offset)?,
+                        Term::List(self.operands(bif_args, offset)?),
+                        self.operand(dst, offset)?,
+                    ],
+                )
+            }
+            ("gc_bif1" | "gc_bif2" | "gc_bif3", [fail, live, Unsigned(bif), rest @ ..]) => {
+                let (dst, bif_args) = rest.split_last().ok_or(Error::InvalidInstruction {
+                    offset,
+                    reason: "missing destination",
+                })?;
+                tuple(
+                    "gc_bif",
+                    vec![
+                        self.bif_name(*bif, offset)?,
+                        self.operand(fail, offset)?,
+                        self.operand(live, offset)?,
+                        Term::List(self.operands(bif_args, offset)?),
+                        self.operand(dst, offset)?,
+                    ],
+                )
+            }
+            ("make_fun2", [Unsigned(index)]) => {
+                let mut elements = self.fun(*index, offset)?;
+                elements.push(self.fun_entry(*index, offset)?.num_free.into());
+                tuple(name, elements)
+            }
+            ("make_fun3", [Unsigned(index), dst, free]) => {
+                let mut elements = self.fun(*index, offset)?;
+                elements.push(self.operand(dst, offset)?);
+                elements.push(self.operand(free, offset)?);
+                tuple(name, elements)
+            }
+            ("bs_put_string", [Unsigned(len), Unsigned(start)]) => {
+                let string = self.string(*start, *len, offset)?;
+                let string = string.iter().map(|&b| Term::Integer(i64::from(b)));
+                tuple(
+                    name,
+                    vec![
+                        uint(*len),
+                        tuple("string", vec![Term::List(string.collect())]),
+                    ],
+                )
+            }
+            ("bs_match_string", [fail, ctx, Unsigned(bits), Unsigned(start)]) => {
+                let string = self.string(*start, bits.div_ceil(8), offset)?;
+                tuple(
+                    "test",
+                    vec![
+                        Term::atom(name),
+                        self.operand(fail, offset)?,
+                        Term::List(vec![
+                            self.operand(ctx, offset)?,
+                            uint(*bits),
+                            tuple("string", vec![Term::Binary(string.to_vec())]),
+                        ]),
+                    ],
+                )
+            }
+            ("has_map_fields", [fail, src, list]) => tuple(
+                "test",
+                vec![
+                    Term::atom(name),
+                    self.operand(fail, offset)?,
+                    self.operand(src, offset)?,
+                    self.operand(list, offset)?,
+                ],
+            ),
+            (_, [fail @ Label(_), rest @ ..]) if TESTS.contains(&name) => tuple(
+                "test",
+                vec![
+                    Term::atom(name),
+                    self.operand(fail, offset)?,
+                    Term::List(self.operands(rest, offset)?),
+                ],
+            ),
+            (_, []) => Term::atom(name),
+            _ => tuple(name, self.operands(args, offset)?),
+        };
+        Ok(term)
+    }
+
+    /// Returns `operand` as a term in the form of `erlc -S` (e.g., `{x,0}` and `{atom,foo}`).
+    ///
+    /// `offset` is the byte offset of the instruction reported in `Error::InvalidInstruction`.
+    pub fn operand(&self, operand: &Operand, offset: usize) -> Result<Term> {
+        let term = match *operand {
+            Operand::Unsigned(n) => uint(n),
+            Operand::Integer(n) => tuple("integer", vec![Term::Integer(n)]),
+            Operand::BigInteger {
+                negative,
+                ref magnitude,
+            } => tuple(
+                "integer",
+                vec![Term::BigInteger {
+                    negative,
+                    magnitude: magnitude.clone(),
+                }],
+            ),
+            Operand::Atom(0) => Term::atom("nil"),
+            Operand::Atom(id) => tuple("atom", vec![self.atom(id)?]),
+            Operand::X(n) => tuple("x", vec![n.into()]),
+            Operand::Y(n) => tuple("y", vec![n.into()]),
+            Operand::Label(n) => tuple("f", vec![n.into()]),
+            Operand::Character(n) => tuple("char", vec![n.into()]),
+            Operand::List(ref elements) => {
+                tuple("list", vec![Term::List(self.operands(elements, offset)?)])
+            }
+            Operand::FloatRegister(n) => tuple("fr", vec![n.into()]),
+            Operand::AllocList(ref pairs) => {
+                let pairs = pairs
+                    .iter()
+                    .map(|&(kind, n)| {
+                        let kind = match kind {
+                            0 => "words",
+                            1 => "floats",
+                            2 => "funs",
+                            _ => {
+                                return Err(Error::InvalidInstruction {
+                                    offset,
+                                    reason: "unknown allocation kind",
+                                })
+                            }
+                        };
+                        Ok(Term::Tuple(vec![Term::atom(kind), n.into()]))
+                    })
+                    .collect::<Result<_>>()?;
+                tuple("alloc", vec![Term::List(pairs)])
+            }
+            Operand::Literal(index) => {
+                let literal =
+                    self.literals
+                        .get(index as usize)
+                        .ok_or(Error::InvalidInstruction {
+                            offset,
+                            reason: "unknown literal",
+                        })?;
+                tuple("literal", vec![literal.clone()])
+            }
+            Operand::TypedRegister(ref register, index) => {
+                tuple("tr", vec![self.operand(register, offset)?, index.into()])
+            }
+        };
+        Ok(term)
+    }
+
+    fn operands(&self, operands: &[Operand], offset: usize) -> Result<Vec<Term>> {
+        operands.iter().map(|o| self.operand(o, offset)).collect()
+    }
+
+    fn atom(&self, id: AtomId) -> Result<Term> {
+        Ok(Term::atom(self.atoms.name(id)?))
+    }
+
+    fn exports_term(&self) -> Result<Term> {
+        let mut exports = Vec::new();
+        for export in self.exports.iter().flat_map(|c| &c.exports) {
+            exports.push((self.atoms.name(export.function)?, export.arity));
+        }
+        exports.sort();
+        let exports = exports
+            .into_iter()
+            .map(|(name, arity)| Term::Tuple(vec![Term::atom(name), arity.into()]));
+        Ok(Term::List(exports.collect()))
+    }
+
+    fn location(&self, index: u64, offset: usize) -> Result<Term> {
+        let location = self
+            .locations
+            .get(index as usize)
+            .ok_or(Error::InvalidInstruction {
+                offset,
+                reason: "unknown line",
+            })?;
+        let Some(location) = location else {
+            return Ok(Term::List(Vec::new()));
+        };
+        let file = self.file_name(location, offset)?;
+        let file = file.chars().map(|c| Term::Integer(i64::from(u32::from(c))));
+        Ok(Term::List(vec![Term::Tuple(vec![
+            Term::atom("location"),
+            Term::List(file.collect()),
+            location.line.into(),
+        ])]))
+    }
+
+    fn file_name(&self, location: &Location, offset: usize) -> Result<&str> {
+        self.file_names
+            .get(location.file as usize)
+            .map(|s| s.as_str())
+            .ok_or(Error::InvalidInstruction {
+                offset,
+                reason: "unknown file",
+            })
+    }
+
+    fn write_source_line(&self, out: &mut String, index: u64) {
+        let Some(Some(location)) = self.locations.get(index as usize) else {
+            return;
+        };
+        let Ok(file) = self.file_name(location, 0) else {
+            return;
+        };
+        let text = self
+            .sources
+            .get(file)
+            .and_then(|lines| lines.get((location.line as usize).checked_sub(1)?));
+        if let Some(text) = text {
+            let _ = writeln!(out, "%% {}:{}: {}", file, location.line, text);
+        }
+    }
+
+    fn extfunc(&self, index: u64, offset: usize) -> Result<Term> {
+        let import = self
+            .imports
+            .as_ref()
+            .and_then(|c| c.imports.get(index as usize))
+            .ok_or(Error::InvalidInstruction {
+                offset,
+                reason: "unknown import",
+            })?;
+        Ok(tuple(
+            "extfunc",
+            vec![
+                self.atom(import.module)?,
+                self.atom(import.function)?,
+                import.arity.into(),
+            ],
+        ))
+    }
+
+    fn bif_name(&self, index: u64, offset: usize) -> Result<Term> {
+        match self.extfunc(index, offset)? {
+            Term::Tuple(mut elements) => Ok(elements.swap_remove(2)),
+            _ => unreachable!(),
+        }
+    }
+
+    fn fun_entry(&self, index: u64, offset: usize) -> Result<&crate::parts::Function> {
+        self.funs
+            .as_ref()
+            .and_then(|c| c.functions.get(index as usize))
+            .ok_or(Error::InvalidInstruction {
+                offset,
+                reason: "unknown fun",
+            })
+    }
+
+    /// Returns `[{f,Label},Index,OldUniq]` of the fun.
+    fn fun(&self, index: u64, offset: usize) -> Result<Vec<Term>> {
+        let fun = self.fun_entry(index, offset)?;
+        Ok(vec![
+            tuple("f", vec![fun.label.into()]),
+            fun.index.into(),
+            fun.old_uniq.into(),
+        ])
+    }
+
+    fn string(&self, start: u64, len: u64, offset: usize) -> Result<&[u8]> {
+        self.strings
+            .as_ref()
+            .and_then(|c| {
+                let start = usize::try_from(start).ok()?;
+                let end = start.checked_add(usize::try_from(len).ok()?)?;
+                c.strings.get(start..end)
+            })
+            .ok_or(Error::InvalidInstruction {
+                offset,
+                reason: "string out of range",
+            })
+    }
+}
+
+fn uint(n: u64) -> Term {
+    match i64::try_from(n) {
+        Ok(n) => Term::Integer(n),
+        Err(_) => Term::BigInteger {
+            negative: false,
+            magnitude: n.to_le_bytes().to_vec(),
+        },
+    }
+}
+
+fn tuple(name: &str, mut elements: Vec<Term>) -> Term {
+    elements.insert(0, Term::atom(name));
+    Term::Tuple(elements)
+}
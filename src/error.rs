@@ -1,14 +1,20 @@
-use std::io::Error as IoError;
-use std::str::Utf8Error;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::str::Utf8Error;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
 
+use crate::beam_file::CHUNK_HEADER_SIZE;
 use crate::chunk::Id as ChunkId;
+use crate::io::Error as IoError;
+use crate::Limit;
 
 #[derive(Debug, ::thiserror::Error)]
 pub enum Error {
-    #[error("Error::Io")]
+    #[error("Error::Io: {0}")]
     Io(#[source] IoError),
 
-    #[error("Error::InvalidString")]
+    #[error("Error::InvalidString: {0}")]
     InvalidString(#[source] Utf8Error),
 
     #[error("Error::UnexpectedMagicNumber: magic_number - {:?}", magic_number)]
@@ -17,8 +23,169 @@ pub enum Error {
     #[error("Error::UnexpectedFormType: form_type - {:?}", form_type)]
     UnexpectedFormType { form_type: [u8; 4] },
 
-    #[error("Error::UnexpectedChunk: id - {:?}, expected - {:?}", id, expected)]
+    #[error(
+        "Error::UnexpectedChunk: id - {:?}, expected - {:?}",
+        id.escape_ascii().to_string(),
+        expected.escape_ascii().to_string()
+    )]
     UnexpectedChunk { id: ChunkId, expected: ChunkId },
+
+    #[error("Error::MissingChunk: id - {:?}", id.escape_ascii().to_string())]
+    MissingChunk { id: ChunkId },
+
+    #[error(
+        "Error::LimitExceeded: limit - {}, value - {}, max - {}",
+        limit,
+        value,
+        max
+    )]
+    LimitExceeded { limit: Limit, value: u64, max: u64 },
+
+    #[error(
+        "Error::TruncatedPayload: expected - {} bytes, actual - {} bytes",
+        expected,
+        actual
+    )]
+    TruncatedPayload { expected: u64, actual: u64 },
+
+    #[error("Error::UnknownAtom: id - {}", id)]
+    UnknownAtom { id: u32 },
+
+    #[error("Error::AtomTooLong: index - {}, len - {}", index, len)]
+    AtomTooLong { index: usize, len: usize },
+
+    #[error("Error::TooLarge: what - {}, size - {}", what, size)]
+    TooLarge { what: &'static str, size: u64 },
+
+    #[error("Error::TrailingGarbage: offset - {}, size - {}", offset, size)]
+    TrailingGarbage { offset: u64, size: u64 },
+
+    #[error("Error::InvalidArchive: reason - {}", reason)]
+    InvalidArchive { reason: &'static str },
+
+    #[error("Error::UnsupportedCompression: method - {}", method)]
+    UnsupportedCompression { method: u16 },
+
+    #[error(
+        "Error::ChecksumMismatch: expected - {:#010x}, actual - {:#010x}",
+        expected,
+        actual
+    )]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
+    #[error("Error::InvalidEscript: reason - {}", reason)]
+    InvalidEscript { reason: &'static str },
+
+    #[error("Error::InvalidTerm: reason - {}", reason)]
+    InvalidTerm { reason: &'static str },
+
+    #[error("Error::InvalidSyntax: line - {}, reason - {}", line, reason)]
+    InvalidSyntax { line: usize, reason: &'static str },
+
+    #[error("Error::UnsupportedTerm: tag - {}", tag)]
+    UnsupportedTerm { tag: u8 },
+
+    #[error("Error::UnknownOpcode: offset - {}, opcode - {}", offset, opcode)]
+    UnknownOpcode { offset: usize, opcode: u8 },
+
+    #[error("Error::InvalidInstruction: offset - {}, reason - {}", offset, reason)]
+    InvalidInstruction { offset: usize, reason: &'static str },
+
+    #[error("Error::InvalidAssembly: line - {}, reason - {}", line, reason)]
+    InvalidAssembly { line: usize, reason: &'static str },
+
+    #[error("Error::InvalidAssemblyTerm: reason - {}", reason)]
+    InvalidAssemblyTerm { reason: &'static str },
+
+    #[error("{:?} at offset {}: {}", id.escape_ascii().to_string(), offset, source)]
+    InChunk {
+        id: ChunkId,
+        offset: u64,
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// An error in the `index`-th entry of a table.
+    ///
+    /// Within `InChunk`, `offset` is the absolute offset of the entry in the file.
+    /// Otherwise, it is the offset in the chunk data (e.g., for `Chunk::decode_data`),
+    /// in the index file for `BeamIndex`, or in the bytecode for `Assembly::assemble`.
+    /// For the `"LitT"` chunk, it is always the offset in the decompressed literal table.
+    #[error("entry #{} at offset {}: {}", index, offset, source)]
+    InEntry {
+        index: usize,
+        offset: u64,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("{}/{}: {}", name, arity, source)]
+    InFunction {
+        name: String,
+        arity: u32,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("{}: {}", name, source)]
+    InArchiveEntry {
+        name: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[cfg(feature = "std")]
+    #[error("{}: {}", path.display(), source)]
+    InFile {
+        path: PathBuf,
+        #[source]
+        source: Box<Error>,
+    },
+}
+impl Error {
+    /// Wraps `source` in `InChunk`, making the offset of an entry absolute (see `InEntry`).
+    ///
+    /// `offset` is the absolute offset of the chunk header.
+    pub(crate) fn in_chunk(id: ChunkId, offset: u64, source: Error) -> Self {
+        let source = match source {
+            Error::InEntry {
+                index,
+                offset: entry_offset,
+                source,
+            } if &id != b"LitT" => Error::InEntry {
+                index,
+                offset: offset + CHUNK_HEADER_SIZE + entry_offset,
+                source,
+            },
+            source => source,
+        };
+        Error::InChunk {
+            id,
+            offset,
+            source: Box::new(source),
+        }
+    }
+
+    /// Returns the innermost error, skipping the context variants (e.g., `InChunk` and `InFile`).
+    ///
+    /// ```
+    /// use beam_file::{Error, RawBeamFile};
+    ///
+    /// let e = RawBeamFile::from_file("no_such_file.beam").unwrap_err();
+    /// assert!(matches!(e, Error::InFile { .. }));
+    /// assert!(matches!(e.root_cause(), Error::Io(_)));
+    /// ```
+    pub fn root_cause(&self) -> &Error {
+        match *self {
+            Error::InChunk { ref source, .. }
+            | Error::InEntry { ref source, .. }
+            | Error::InFunction { ref source, .. }
+            | Error::InArchiveEntry { ref source, .. } => source.root_cause(),
+            #[cfg(feature = "std")]
+            Error::InFile { ref source, .. } => source.root_cause(),
+            _ => self,
+        }
+    }
 }
 
 impl From<IoError> for Error {
@@ -0,0 +1,267 @@
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufReader, BufWriter};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::archive::{ArchivedBeam, ZipArchive, ZipWriter};
+use crate::chunk::Chunk;
+use crate::io::{Read, Write};
+use crate::{BeamFile, DecodeLimits, Error, Limit, Result};
+
+/// An escript (i.e., a header of a few lines followed by Erlang source code, a BEAM file or a ZIP archive).
+///
+/// ```
+/// use beam_file::{Escript, EscriptBody, RawBeamFile};
+/// use beam_file::chunk::RawChunk;
+///
+/// let beam = RawBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let mut escript = Escript::new(EscriptBody::archive([("test.beam", &beam)]).unwrap());
+/// escript.emu_args = Some("-sname test".to_owned());
+///
+/// let mut bytes = Vec::new();
+/// escript.to_writer(&mut bytes).unwrap();
+/// assert!(bytes.starts_with(b"#!/usr/bin/env escript\n"));
+///
+/// let escript = Escript::from_slice(&bytes).unwrap();
+/// assert_eq!(Some("-sname test"), escript.emu_args.as_deref());
+/// let modules = escript.modules::<RawChunk>().unwrap();
+/// assert_eq!("test.beam", modules[0].path);
+/// assert_eq!(beam.chunks, modules[0].beam.chunks);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escript {
+    /// The interpreter following `#!` in the first line (e.g., `"/usr/bin/env escript"`).
+    pub shebang: Option<String>,
+
+    /// The comment line following `%%` (without the space after it).
+    pub comment: Option<String>,
+
+    /// The arguments for the emulator following `%%!`.
+    pub emu_args: Option<String>,
+
+    /// The bytes following the header.
+    pub body: EscriptBody,
+}
+impl Escript {
+    /// Makes an `Escript` with the same header as `escript:create/2` writes by default.
+    pub fn new(body: EscriptBody) -> Self {
+        Escript {
+            shebang: Some("/usr/bin/env escript".to_owned()),
+            comment: Some("This is an -*- erlang -*- file".to_owned()),
+            emu_args: None,
+            body,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let read = || {
+            let f = File::open(path)?;
+            Self::from_reader(BufReader::new(f))
+        };
+        read().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+
+    /// Reads an escript from `reader` to the end.
+    ///
+    /// This imposes no limit on the size of the escript (see `from_reader_with_limits`).
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_reader_with_limits(reader, &DecodeLimits::default())
+    }
+
+    /// Reads an escript from `reader` to the end if its size is within `limits.max_file_size`.
+    pub fn from_reader_with_limits<R: Read>(reader: R, limits: &DecodeLimits) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader
+            .take(limits.max_file_size.saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        limits.check(Limit::FileSize, bytes.len() as u64)?;
+        Self::from_slice(&bytes)
+    }
+
+    /// Parses the header lines in the same way as `escript:extract/2`.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let mut rest = bytes;
+        let mut shebang = None;
+        let mut comment = None;
+        let mut emu_args = None;
+        if let Some(line) = rest.strip_prefix(b"#!") {
+            let (line, next) = split_line(line)?;
+            shebang = Some(line.to_owned());
+            rest = next;
+
+            // The second line is either a comment or the emulator arguments,
+            // and the third line can be the emulator arguments following a comment
+            if rest.starts_with(b"%") && !rest.starts_with(b"%%!") {
+                let (line, next) = split_line(&rest[1..])?;
+                let line = line.strip_prefix('%').unwrap_or(line);
+                comment = Some(line.strip_prefix(' ').unwrap_or(line).to_owned());
+                rest = next;
+            }
+            if let Some(line) = rest.strip_prefix(b"%%!") {
+                let (line, next) = split_line(line)?;
+                emu_args = Some(line.to_owned());
+                rest = next;
+            }
+        }
+
+        let body = if rest.starts_with(b"FOR1") {
+            EscriptBody::Beam(rest.to_vec())
+        } else if rest.starts_with(b"PK") {
+            EscriptBody::Archive(rest.to_vec())
+        } else {
+            EscriptBody::Source(rest.to_vec())
+        };
+        Ok(Escript {
+            shebang,
+            comment,
+            emu_args,
+            body,
+        })
+    }
+
+    /// Decodes the embedded BEAM files.
+    ///
+    /// For an archive, these are the entries with the `.beam` extension, along with their paths.
+    /// For a BEAM file, the path is empty.
+    /// For source code, this returns no BEAM files.
+    pub fn modules<C: Chunk>(&self) -> Result<Vec<ArchivedBeam<C>>> {
+        self.modules_with_limits(&DecodeLimits::default())
+    }
+
+    /// Decodes the embedded BEAM files within `limits` (see `archive` for the limits on archives).
+    pub fn modules_with_limits<C: Chunk>(
+        &self,
+        limits: &DecodeLimits,
+    ) -> Result<Vec<ArchivedBeam<C>>> {
+        match self.body {
+            EscriptBody::Source(_) => Ok(Vec::new()),
+            EscriptBody::Beam(ref bytes) => Ok(vec![ArchivedBeam {
+                path: String::new(),
+                beam: BeamFile::from_reader_with_limits(&bytes[..], limits)?,
+            }]),
+            EscriptBody::Archive(ref bytes) => {
+                let archive = ZipArchive::new(bytes)?;
+                archive
+                    .entries()
+                    .iter()
+                    .filter(|e| e.name.ends_with(".beam"))
+                    .map(|e| {
+                        let decode = || {
+                            let data = archive.read_with_limits(e, limits)?;
+                            BeamFile::from_reader_with_limits(&data[..], limits)
+                        };
+                        let beam = decode().map_err(|source| Error::InArchiveEntry {
+                            name: e.name.clone(),
+                            source: Box::new(source),
+                        })?;
+                        Ok(ArchivedBeam {
+                            path: e.name.clone(),
+                            beam,
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_writer(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+        let lines = [
+            ("#!", &self.shebang),
+            ("%% ", &self.comment),
+            ("%%!", &self.emu_args),
+        ];
+        for (prefix, line) in lines {
+            if let Some(line) = line {
+                if line.contains('\n') {
+                    return Err(Error::InvalidEscript {
+                        reason: "header line containing a newline",
+                    });
+                }
+                writer.write_all(prefix.as_bytes())?;
+                writer.write_all(line.as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        writer.write_all(self.body.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The body of an `Escript`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscriptBody {
+    /// Erlang source code.
+    Source(Vec<u8>),
+
+    /// A BEAM file.
+    Beam(Vec<u8>),
+
+    /// A ZIP archive (typically containing BEAM files).
+    Archive(Vec<u8>),
+}
+impl EscriptBody {
+    /// Makes a body consisting of `beam`.
+    pub fn beam<C: Chunk>(beam: &BeamFile<C>) -> Result<Self> {
+        let mut bytes = Vec::new();
+        beam.to_writer(&mut bytes)?;
+        Ok(EscriptBody::Beam(bytes))
+    }
+
+    /// Makes a body of a ZIP archive containing `beams`, where each is paired with its entry name
+    /// (e.g., `"foo.beam"`).
+    pub fn archive<'a, C, I>(beams: I) -> Result<Self>
+    where
+        C: Chunk + 'a,
+        I: IntoIterator<Item = (&'a str, &'a BeamFile<C>)>,
+    {
+        let mut writer = ZipWriter::new(Vec::new());
+        let mut bytes = Vec::new();
+        for (name, beam) in beams {
+            bytes.clear();
+            beam.to_writer(&mut bytes)?;
+            writer.add(name, &bytes)?;
+        }
+        Ok(EscriptBody::Archive(writer.finish()?))
+    }
+
+    /// Returns the bytes of the body.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            EscriptBody::Source(bytes) | EscriptBody::Beam(bytes) | EscriptBody::Archive(bytes) => {
+                bytes
+            }
+        }
+    }
+}
+
+fn split_line(bytes: &[u8]) -> Result<(&str, &[u8])> {
+    let end = bytes
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or(Error::InvalidEscript {
+            reason: "unterminated header line",
+        })?;
+    let line = str::from_utf8(&bytes[..end])?;
+    Ok((line.strip_suffix('\r').unwrap_or(line), &bytes[end + 1..]))
+}
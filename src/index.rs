@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::chunk::aux::{self, to_u32};
+use crate::chunk::{AtomChunk, AttrChunk, ExpTChunk, ImpTChunk, StandardChunk};
+use crate::io_ext::{ReadExt, WriteExt};
+use crate::parts::{Arity, ExternalTermFormatBinary};
+use crate::scan::{self, find_beam_files};
+use crate::{BeamFile, Error, RawBeamFile, Result};
+
+const MAGIC_NUMBER: &[u8; 4] = b"BIX1";
+
+/// A cache of the metadata of BEAM files, which can be stored on disk.
+///
+/// `update` re-parses only the files whose modification time or size have changed
+/// since the last update, so a large collection of BEAM files can be indexed repeatedly
+/// without decoding all of them every time.
+///
+/// ```
+/// use beam_file::BeamIndex;
+///
+/// let mut index = BeamIndex::new();
+/// let update = index.update(&["tests/testdata"]);
+/// assert_eq!(2, update.parsed.len());
+///
+/// let mut buf = Vec::new();
+/// index.to_writer(&mut buf).unwrap();
+/// let mut index = BeamIndex::from_reader(&buf[..]).unwrap();
+/// let update = index.update(&["tests/testdata"]);
+/// assert_eq!(0, update.parsed.len());
+/// assert_eq!(2, update.reused);
+///
+/// let entry = index.get("tests/testdata/test.beam").unwrap();
+/// assert_eq!("test", entry.module);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BeamIndex {
+    entries: BTreeMap<PathBuf, IndexEntry>,
+
+    /// The number of worker threads used to parse the files.
+    ///
+    /// If `0`, the available parallelism of the machine is used.
+    pub threads: usize,
+}
+impl BeamIndex {
+    /// Makes an empty `BeamIndex`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let read = || {
+            let f = File::open(path)?;
+            Self::from_reader(BufReader::new(f))
+        };
+        read().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut reader = aux::CountingReader::new(reader);
+        let mut magic_number = [0; 4];
+        reader.read_exact(&mut magic_number)?;
+        if &magic_number != MAGIC_NUMBER {
+            return Err(Error::UnexpectedMagicNumber { magic_number });
+        }
+        let count = reader.read_u32()?;
+        let mut entries = BTreeMap::new();
+        for index in 0..count as usize {
+            let entry = aux::decode_entry(index, reader.position, || decode_entry(&mut reader))?;
+            entries.insert(entry.path.clone(), entry);
+        }
+        Ok(BeamIndex {
+            entries,
+            threads: 0,
+        })
+    }
+
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_writer(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(MAGIC_NUMBER)?;
+        writer.write_u32(to_u32("index entry count", self.entries.len())?)?;
+        for entry in self.entries.values() {
+            encode_entry(&mut writer, entry)?;
+        }
+        Ok(())
+    }
+
+    /// Brings the index up to date with the BEAM files under `roots`.
+    ///
+    /// `roots` are walked in the same way as `Scanner::scan`.
+    /// The entries of the files which are no longer found under `roots`,
+    /// or which could not be parsed, are removed from the index.
+    /// The entries of the files whose metadata could not be read are kept as they are.
+    pub fn update<P: AsRef<Path>>(&mut self, roots: &[P]) -> IndexUpdate {
+        let (paths, mut errors) = find_beam_files(roots);
+        let mut update = IndexUpdate::default();
+        let mut entries = BTreeMap::new();
+        let mut stale = Vec::new();
+        for path in paths {
+            let stat = fs::metadata(&path).and_then(|m| Ok((m.modified()?, m.len())));
+            let (mtime, size) = match stat {
+                Ok(stat) => stat,
+                Err(e) => {
+                    // The cached entry is kept since the file may still exist
+                    if let Some(entry) = self.entries.remove(&path) {
+                        entries.insert(path.clone(), entry);
+                    }
+                    let e = Error::InFile {
+                        path: path.clone(),
+                        source: Box::new(e.into()),
+                    };
+                    errors.push((path, e));
+                    continue;
+                }
+            };
+            match self.entries.remove(&path) {
+                Some(entry) if entry.mtime == mtime && entry.size == size => {
+                    update.reused += 1;
+                    entries.insert(path, entry);
+                }
+                cached => stale.push((path, cached)),
+            }
+        }
+
+        let results = scan::parallel_map(&stale, self.threads, |(path, cached)| {
+            IndexEntry::from_file(path, cached.as_ref())
+        });
+        for ((path, _), result) in stale.into_iter().zip(results) {
+            match result {
+                Ok(entry) => {
+                    update.parsed.push(path.clone());
+                    entries.insert(path, entry);
+                }
+                Err(e) => errors.push((path, e)),
+            }
+        }
+
+        update.removed = self.entries.keys().cloned().collect();
+        update.errors = errors;
+        self.entries = entries;
+        update
+    }
+
+    /// Returns the entry of the file at `path`.
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&IndexEntry> {
+        self.entries.get(path.as_ref())
+    }
+
+    /// Returns the entries in the order of their paths.
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values()
+    }
+}
+
+/// The result of `BeamIndex::update`.
+#[derive(Debug, Default)]
+pub struct IndexUpdate {
+    /// The number of entries which were up to date.
+    pub reused: usize,
+
+    /// The files which were (re-)parsed.
+    pub parsed: Vec<PathBuf>,
+
+    /// The files which were removed from the index because they are no longer found.
+    pub removed: Vec<PathBuf>,
+
+    /// The files and directories which could not be read.
+    pub errors: Vec<(PathBuf, Error)>,
+}
+
+/// The metadata of a BEAM file in a `BeamIndex`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The path of the file.
+    pub path: PathBuf,
+
+    /// The modification time of the file.
+    pub mtime: SystemTime,
+
+    /// The size of the file in bytes.
+    pub size: u64,
+
+    /// The MD5 digest of the whole file.
+    pub md5: [u8; 16],
+
+    /// The module name.
+    pub module: String,
+
+    /// The exported functions.
+    pub exports: Vec<IndexedFunction>,
+
+    /// The imported functions.
+    pub imports: Vec<IndexedFunction>,
+
+    /// The `"Attr"` chunk (i.e., `term_to_binary(Module:module_info(attributes))`).
+    ///
+    /// This is empty if the file has no `"Attr"` chunk.
+    pub attributes: ExternalTermFormatBinary,
+}
+impl IndexEntry {
+    fn from_file(path: &Path, cached: Option<&IndexEntry>) -> Result<Self> {
+        let read = || {
+            let f = File::open(path)?;
+            let metadata = f.metadata()?;
+            let mut bytes = Vec::new();
+            BufReader::new(f).read_to_end(&mut bytes)?;
+            let md5 = md5::compute(&bytes).0;
+            if let Some(cached) = cached.filter(|c| c.md5 == md5) {
+                // Only the modification time has changed
+                return Ok(IndexEntry {
+                    mtime: metadata.modified()?,
+                    size: metadata.len(),
+                    ..cached.clone()
+                });
+            }
+
+            let mut raw = RawBeamFile::from_reader(&bytes[..])?;
+            raw.chunks
+                .retain(|c| matches!(&c.id, b"Atom" | b"AtU8" | b"ExpT" | b"ImpT" | b"Attr"));
+            let beam: BeamFile<StandardChunk> = raw.decode()?;
+            let atoms = beam
+                .chunk::<AtomChunk>()
+                .ok_or(Error::MissingChunk { id: *b"Atom" })?;
+            let module = atoms.name(1)?.to_owned();
+            let exports = beam.chunk::<ExpTChunk>().map_or(Ok(Vec::new()), |c| {
+                c.exports
+                    .iter()
+                    .map(|e| {
+                        Ok(IndexedFunction::new(
+                            &module,
+                            atoms.name(e.function)?,
+                            e.arity,
+                        ))
+                    })
+                    .collect::<Result<_>>()
+            })?;
+            let imports = beam.chunk::<ImpTChunk>().map_or(Ok(Vec::new()), |c| {
+                c.imports
+                    .iter()
+                    .map(|i| {
+                        let module = atoms.name(i.module)?;
+                        Ok(IndexedFunction::new(
+                            module,
+                            atoms.name(i.function)?,
+                            i.arity,
+                        ))
+                    })
+                    .collect::<Result<_>>()
+            })?;
+            let attributes = beam
+                .chunk::<AttrChunk>()
+                .map(|c| c.term.clone())
+                .unwrap_or_default();
+            Ok(IndexEntry {
+                path: path.to_path_buf(),
+                mtime: metadata.modified()?,
+                size: metadata.len(),
+                md5,
+                module,
+                exports,
+                imports,
+                attributes,
+            })
+        };
+        read().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+}
+
+/// A function in an `IndexEntry` (i.e., `Module:Function/Arity`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexedFunction {
+    pub module: String,
+    pub function: String,
+    pub arity: Arity,
+}
+impl IndexedFunction {
+    fn new(module: &str, function: &str, arity: Arity) -> Self {
+        IndexedFunction {
+            module: module.to_owned(),
+            function: function.to_owned(),
+            arity,
+        }
+    }
+}
+
+fn decode_entry<R: Read>(mut reader: R) -> Result<IndexEntry> {
+    let path = read_path(&mut reader)?;
+    let secs = reader.read_u64()?;
+    let nanos = reader.read_u32()?;
+    let mtime = UNIX_EPOCH
+        .checked_add(Duration::new(secs, nanos))
+        .ok_or(Error::TooLarge {
+            what: "modification time",
+            size: secs,
+        })?;
+    let size = reader.read_u64()?;
+    let mut md5 = [0; 16];
+    reader.read_exact(&mut md5)?;
+    let module = read_string(&mut reader)?;
+    let exports = read_functions(&mut reader)?;
+    let imports = read_functions(&mut reader)?;
+    let attributes = read_bytes(&mut reader)?;
+    Ok(IndexEntry {
+        path,
+        mtime,
+        size,
+        md5,
+        module,
+        exports,
+        imports,
+        attributes,
+    })
+}
+
+fn encode_entry<W: Write>(mut writer: W, entry: &IndexEntry) -> Result<()> {
+    write_path(&mut writer, &entry.path)?;
+    // Times before the epoch are not expected for BEAM files, so they are clamped to it
+    let mtime = entry.mtime.duration_since(UNIX_EPOCH).unwrap_or_default();
+    writer.write_u64(mtime.as_secs())?;
+    writer.write_u32(mtime.subsec_nanos())?;
+    writer.write_u64(entry.size)?;
+    writer.write_all(&entry.md5)?;
+    write_bytes(&mut writer, entry.module.as_bytes())?;
+    for functions in [&entry.exports, &entry.imports] {
+        writer.write_u32(to_u32("function count", functions.len())?)?;
+        for f in functions {
+            write_bytes(&mut writer, f.module.as_bytes())?;
+            write_bytes(&mut writer, f.function.as_bytes())?;
+            writer.write_u32(f.arity)?;
+        }
+    }
+    write_bytes(&mut writer, &entry.attributes)?;
+    Ok(())
+}
+
+fn read_functions<R: Read>(mut reader: R) -> Result<Vec<IndexedFunction>> {
+    let count = reader.read_u32()?;
+    (0..count)
+        .map(|_| {
+            Ok(IndexedFunction {
+                module: read_string(&mut reader)?,
+                function: read_string(&mut reader)?,
+                arity: reader.read_u32()?,
+            })
+        })
+        .collect()
+}
+
+/// Paths are stored as the raw bytes on Unix, and as UTF-8 strings elsewhere.
+#[cfg(unix)]
+fn read_path<R: Read>(reader: R) -> Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(read_bytes(
+        reader,
+    )?)))
+}
+
+#[cfg(not(unix))]
+fn read_path<R: Read>(reader: R) -> Result<PathBuf> {
+    Ok(PathBuf::from(read_string(reader)?))
+}
+
+#[cfg(unix)]
+fn write_path<W: Write>(writer: W, path: &Path) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    write_bytes(writer, path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_path<W: Write>(writer: W, path: &Path) -> Result<()> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "non-UTF-8 path"))?;
+    write_bytes(writer, path.as_bytes())
+}
+
+fn read_string<R: Read>(reader: R) -> Result<String> {
+    let bytes = read_bytes(reader)?;
+    String::from_utf8(bytes).map_err(|e| e.utf8_error().into())
+}
+
+fn read_bytes<R: Read>(mut reader: R) -> Result<Vec<u8>> {
+    let size = reader.read_u32()?;
+    Ok(aux::read_bytes(reader, size as usize)?)
+}
+
+fn write_bytes<W: Write>(mut writer: W, bytes: &[u8]) -> Result<()> {
+    writer.write_u32(to_u32("string size", bytes.len())?)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
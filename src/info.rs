@@ -0,0 +1,172 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::BufReader;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::beam_file::{self, Header};
+use crate::chunk::{aux, Id};
+use crate::io::{self, Read};
+use crate::io_ext::ReadExt;
+use crate::{Error, Result};
+
+/// The layout of a BEAM file, as reported by [`beam_lib:info/1`].
+///
+/// The chunk data is not decoded except for the module name.
+///
+/// [`beam_lib:info/1`]: http://erlang.org/doc/man/beam_lib.html#info-1
+///
+/// ```
+/// use beam_file::BeamInfo;
+///
+/// let info = BeamInfo::from_file("tests/testdata/test.beam").unwrap();
+/// assert_eq!(Some("test"), info.module.as_deref());
+/// assert_eq!(b"Atom", &info.chunks[0].id);
+/// assert_eq!(20, info.chunks[0].offset);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BeamInfo {
+    /// The module name (i.e., the first atom in the `"Atom"` or `"AtU8"` chunk).
+    pub module: Option<String>,
+
+    /// The payload size in the `"FOR1"` header.
+    pub payload_size: u32,
+
+    /// The chunks in the order they appear in the file.
+    pub chunks: Vec<ChunkInfo>,
+}
+impl BeamInfo {
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let read = || {
+            let f = File::open(path)?;
+            Self::from_reader(BufReader::new(f))
+        };
+        read().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        Self::from_reader(bytes)
+    }
+
+    /// Reads the layout of a BEAM file from `reader`.
+    ///
+    /// `reader` is read to the end to report the bytes following the `"FOR1"` payload
+    /// as `Error::TrailingGarbage`, in the same way as `BeamFile::from_file`.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let header = Header::from_reader(&mut reader)?;
+        header.check()?;
+
+        let payload_size = u64::from(header.payload_size);
+        let mut position = 4; // The form type
+        let mut module = None;
+        let mut chunks = Vec::new();
+        while position < payload_size {
+            let offset = position + 8;
+            let rest = payload_size - position;
+            if rest < 8 {
+                return Err(Error::TrailingGarbage { offset, size: rest });
+            }
+
+            let mut id = [0; 4];
+            let chunk =
+                read_chunk(&mut reader, &mut id, offset, rest - 8, &mut module).map_err(|e| {
+                    Error::InChunk {
+                        id,
+                        offset,
+                        source: Box::new(e),
+                    }
+                })?;
+            position += 8 + u64::from(chunk.size) + u64::from(chunk.padding);
+            chunks.push(chunk);
+        }
+
+        let trailing = reader.skip_to_end()?;
+        if trailing != 0 {
+            return Err(Error::TrailingGarbage {
+                offset: payload_size + 8,
+                size: trailing,
+            });
+        }
+        Ok(BeamInfo {
+            module,
+            payload_size: header.payload_size,
+            chunks,
+        })
+    }
+
+    /// Returns the size of the file (i.e., the `"FOR1"` header and payload) in bytes.
+    pub fn file_size(&self) -> u64 {
+        u64::from(self.payload_size) + 8
+    }
+}
+
+/// The location of a chunk in a BEAM file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkInfo {
+    /// The identifier of the chunk.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::id"))]
+    pub id: Id,
+
+    /// The absolute offset of the chunk data (i.e., following the chunk header).
+    pub offset: u64,
+
+    /// The data size of the chunk.
+    pub size: u32,
+
+    /// The number of padding bytes following the data.
+    pub padding: u32,
+}
+impl ChunkInfo {
+    /// Returns the absolute offset of the chunk header.
+    pub fn header_offset(&self) -> u64 {
+        self.offset - 8
+    }
+}
+
+fn read_chunk<R: Read>(
+    mut reader: R,
+    id: &mut Id,
+    offset: u64,
+    rest: u64,
+    module: &mut Option<String>,
+) -> Result<ChunkInfo> {
+    reader.read_exact(id)?;
+    let size = reader.read_u32()?;
+    let padding = aux::padding_size(size);
+    let chunk_size = beam_file::padded_data_size(size, rest)?;
+
+    let mut data = reader.take(chunk_size);
+    if module.is_none() && (id == b"Atom" || id == b"AtU8") {
+        *module = Some(read_module_name(&mut data)?);
+    }
+    data.skip_to_end()?;
+    if data.limit() != 0 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(ChunkInfo {
+        id: *id,
+        offset: offset + 8,
+        size,
+        padding,
+    })
+}
+
+fn read_module_name<R: Read>(mut reader: R) -> Result<String> {
+    let _count = reader.read_u32()?;
+    let len = reader.read_u8()?;
+    let mut buf = vec![0; len as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(str::from_utf8(&buf)?.to_string())
+}
@@ -0,0 +1,62 @@
+//! Big-endian integer helpers for the I/O traits in [`crate::io`].
+use crate::io::{self, Read, Write};
+
+pub(crate) trait ReadExt: Read {
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut buf = [0; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_u16(&mut self) -> io::Result<u16> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0; 4];
+        self.read_exact(&mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Reads and discards bytes until EOF, returning the number of discarded bytes.
+    fn skip_to_end(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 1024];
+        let mut skipped = 0;
+        loop {
+            match self.read(&mut buf) {
+                Ok(0) => return Ok(skipped),
+                Ok(n) => skipped += n as u64,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+impl<R: Read + ?Sized> ReadExt for R {}
+
+pub(crate) trait WriteExt: Write {
+    fn write_u8(&mut self, n: u8) -> io::Result<()> {
+        self.write_all(&[n])
+    }
+
+    fn write_u16(&mut self, n: u16) -> io::Result<()> {
+        self.write_all(&n.to_be_bytes())
+    }
+
+    fn write_u32(&mut self, n: u32) -> io::Result<()> {
+        self.write_all(&n.to_be_bytes())
+    }
+
+    fn write_u64(&mut self, n: u64) -> io::Result<()> {
+        self.write_all(&n.to_be_bytes())
+    }
+}
+impl<W: Write + ?Sized> WriteExt for W {}
@@ -23,18 +23,70 @@
 //!
 //! let chunk = RawChunk{id: *b"Atom", data: Vec::new()}; // NOTICE: The chunk is malformed
 //! let beam = RawBeamFile{chunks: vec![chunk]};
-//! beam.to_file("my.beam").unwrap();
+//! beam.to_file(std::env::temp_dir().join("my.beam")).unwrap();
 //! ```
+//!
+//! # `no_std` Support
+//!
+//! Disabling the default `std` feature makes this crate depend only on `alloc`.
+//! The file-based APIs (e.g., `BeamFile::from_file`) are then unavailable,
+//! and the readers and writers are the traits in [`io`] instead of those in `std::io`.
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+pub mod archive;
+mod asm;
+#[cfg(feature = "tokio")]
+mod async_io;
 mod beam_file;
+pub mod cfg;
 pub mod chunk;
+pub mod code;
+#[cfg(feature = "std")]
+mod code_path;
+mod disasm;
+mod escript;
+#[cfg(feature = "std")]
+mod index;
+mod info;
+mod io_ext;
+pub mod liveness;
+mod lossless;
 pub mod parts;
+#[cfg(feature = "std")]
+mod scan;
+#[cfg(feature = "serde")]
+mod serde_helpers;
+pub mod term;
+pub mod verify;
 
+pub use crate::asm::{Assembly, AssemblyFunction};
+#[cfg(feature = "tokio")]
+pub use crate::async_io::AsyncChunkReader;
 pub use crate::beam_file::BeamFile;
+#[cfg(feature = "std")]
+pub use crate::code_path::{CodeIndex, CodePath, Conflict, ModuleFile};
+pub use crate::disasm::Disassembler;
+pub use crate::escript::{Escript, EscriptBody};
+#[cfg(feature = "std")]
+pub use crate::index::{BeamIndex, IndexEntry, IndexUpdate, IndexedFunction};
+pub use crate::info::{BeamInfo, ChunkInfo};
+pub use crate::lossless::LosslessBeamFile;
+#[cfg(feature = "std")]
+pub use crate::scan::{ScanEntry, ScanProgress, Scanner};
 pub type RawBeamFile = BeamFile<chunk::RawChunk>;
 pub type StandardBeamFile = BeamFile<chunk::StandardChunk>;
+pub type LenientBeamFile = BeamFile<chunk::LenientChunk<chunk::StandardChunk>>;
+
+pub type Result<T> = core::result::Result<T, Error>;
 
-pub type Result<T> = std::result::Result<T, Error>;
+/// The I/O traits used by this crate.
+///
+/// This is `std::io` if the `std` feature is enabled.
+pub use no_std_io2::io;
 
 mod error;
+mod limits;
 pub use error::Error;
+pub use limits::{DecodeLimits, Limit};
@@ -0,0 +1,88 @@
+use core::fmt;
+
+use crate::{Error, Result};
+
+/// Limits applied while decoding a BEAM file.
+///
+/// The default value imposes no limits.
+/// Set the fields explicitly when decoding untrusted input.
+///
+/// ```
+/// use beam_file::{DecodeLimits, Error, StandardBeamFile};
+///
+/// let limits = DecodeLimits {
+///     max_entries: 4,
+///     ..DecodeLimits::default()
+/// };
+/// let f = std::fs::File::open("tests/testdata/test.beam").unwrap();
+/// let e = StandardBeamFile::from_reader_with_limits(f, &limits).unwrap_err();
+/// assert!(matches!(e.root_cause(), Error::LimitExceeded { .. }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// The maximum size of a BEAM file in bytes (including the `"FOR1"` header).
+    pub max_file_size: u64,
+
+    /// The maximum data size of a chunk in bytes.
+    pub max_chunk_size: u64,
+
+    /// The maximum number of entries in a table (e.g., atoms, imports and literals).
+    pub max_entries: u64,
+
+    /// The maximum size of the decompressed data in the `"LitT"` chunk in bytes.
+    pub max_literal_size: u64,
+}
+impl DecodeLimits {
+    /// Makes a `DecodeLimits` which imposes no limits.
+    pub fn unlimited() -> Self {
+        DecodeLimits {
+            max_file_size: u64::MAX,
+            max_chunk_size: u64::MAX,
+            max_entries: u64::MAX,
+            max_literal_size: u64::MAX,
+        }
+    }
+
+    /// Returns the maximum value of `limit`.
+    pub fn max(&self, limit: Limit) -> u64 {
+        match limit {
+            Limit::FileSize => self.max_file_size,
+            Limit::ChunkSize => self.max_chunk_size,
+            Limit::Entries => self.max_entries,
+            Limit::LiteralSize => self.max_literal_size,
+        }
+    }
+
+    pub(crate) fn check(&self, limit: Limit, value: u64) -> Result<()> {
+        let max = self.max(limit);
+        if value > max {
+            Err(Error::LimitExceeded { limit, value, max })
+        } else {
+            Ok(())
+        }
+    }
+}
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// A kind of limit in `DecodeLimits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    FileSize,
+    ChunkSize,
+    Entries,
+    LiteralSize,
+}
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Limit::FileSize => write!(f, "file size"),
+            Limit::ChunkSize => write!(f, "chunk size"),
+            Limit::Entries => write!(f, "entries"),
+            Limit::LiteralSize => write!(f, "literal size"),
+        }
+    }
+}
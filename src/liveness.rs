@@ -0,0 +1,478 @@
+//! Liveness of the registers and the sizes of the stack frames in a function.
+//!
+//! The liveness is computed backward over the control-flow graph from the registers
+//! each instruction reads and writes.
+//! The x registers not covered by the `Live` operand of an instruction which may
+//! garbage-collect (e.g., `test_heap`), and all of them except `{x,0}` after a call, are dead.
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::cfg::ControlFlowGraph;
+use crate::code::{Function, Instruction, Operand};
+use crate::parts;
+
+/// A set of x and y registers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub x: BTreeSet<u32>,
+    pub y: BTreeSet<u32>,
+}
+impl Registers {
+    fn union(&mut self, other: &Registers) {
+        self.x.extend(&other.x);
+        self.y.extend(&other.y);
+    }
+}
+
+/// The amount of heap reserved by `test_heap` and `allocate_heap*`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapNeed {
+    pub words: u32,
+    pub floats: u32,
+    pub funs: u32,
+}
+
+/// The liveness of the registers and the stack frames of a function.
+///
+/// ```
+/// use beam_file::StandardBeamFile;
+/// use beam_file::cfg::ControlFlowGraph;
+/// use beam_file::chunk::FunTChunk;
+/// use beam_file::code;
+/// use beam_file::liveness::Liveness;
+///
+/// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// let funs = &beam.chunk::<FunTChunk>().unwrap().functions;
+/// let hello = &code::functions(&beam).unwrap()[0];
+/// let cfg = ControlFlowGraph::new(hello).unwrap();
+/// let liveness = Liveness::new(hello, &cfg, funs);
+///
+/// // `{x,0}` is captured by `make_fun2` and then replaced with the fun
+/// assert_eq!("make_fun2", hello.instructions[5].name());
+/// assert!(liveness.live_in[5].x.contains(&0));
+/// assert_eq!("call_fun", hello.instructions[7].name());
+/// assert!(liveness.live_in[7].x.contains(&0));
+/// assert!(liveness.live_out[7].x.is_empty());
+/// assert_eq!(Some(0), liveness.frame_sizes[7]);
+/// assert_eq!(None, liveness.frame_sizes[10]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liveness {
+    /// The registers live before each instruction in `Function::instructions`.
+    pub live_in: Vec<Registers>,
+
+    /// The registers live after each instruction in `Function::instructions`.
+    pub live_out: Vec<Registers>,
+
+    /// The size of the stack frame in words before each instruction,
+    /// or `None` if there is no frame (or the instruction is unreachable).
+    pub frame_sizes: Vec<Option<u32>>,
+
+    /// The largest stack frame allocated by the function.
+    pub max_frame_size: u32,
+
+    /// The largest amount of heap reserved at a time.
+    pub max_heap_need: HeapNeed,
+}
+impl Liveness {
+    /// Analyzes `function`, whose control-flow graph is `cfg`.
+    ///
+    /// `funs` is the `"FunT"` chunk which gives the number of the free variables read by
+    /// `make_fun2`. If it is empty, `make_fun2` is assumed to read no registers.
+    pub fn new(function: &Function, cfg: &ControlFlowGraph, funs: &[parts::Function]) -> Self {
+        let effects = function
+            .instructions
+            .iter()
+            .map(|i| Effect::of(i, funs))
+            .collect::<Vec<_>>();
+        let (live_in, live_out) = liveness(function, cfg, &effects);
+        let frame_sizes = frame_sizes(function, cfg);
+
+        let mut max_frame_size = 0;
+        let mut max_heap_need = HeapNeed::default();
+        for instruction in &function.instructions {
+            let (frame, heap) = match (instruction.name(), &instruction.args[..]) {
+                ("allocate" | "allocate_zero", [Operand::Unsigned(n), _]) => (*n, None),
+                ("allocate_heap" | "allocate_heap_zero", [Operand::Unsigned(n), heap, _]) => {
+                    (*n, Some(heap))
+                }
+                ("test_heap", [heap, _]) => (0, Some(heap)),
+                _ => continue,
+            };
+            max_frame_size = max_frame_size.max(frame as u32);
+            let need = heap.map(heap_need).unwrap_or_default();
+            max_heap_need.words = max_heap_need.words.max(need.words);
+            max_heap_need.floats = max_heap_need.floats.max(need.floats);
+            max_heap_need.funs = max_heap_need.funs.max(need.funs);
+        }
+        Liveness {
+            live_in,
+            live_out,
+            frame_sizes,
+            max_frame_size,
+            max_heap_need,
+        }
+    }
+
+    /// Returns the calls (as the indices of the instructions) which have y registers live across them,
+    /// with the registers.
+    pub fn live_across_calls(&self, function: &Function) -> Vec<(usize, BTreeSet<u32>)> {
+        function
+            .instructions
+            .iter()
+            .enumerate()
+            .filter(|(i, instruction)| {
+                is_call(instruction.name()) && !self.live_out[*i].y.is_empty()
+            })
+            .map(|(i, _)| (i, self.live_out[i].y.clone()))
+            .collect()
+    }
+}
+
+/// Returns the amount of heap given by the operand of `test_heap` or `allocate_heap*`.
+pub(crate) fn heap_need(operand: &Operand) -> HeapNeed {
+    let mut need = HeapNeed::default();
+    match *operand {
+        Operand::Unsigned(words) => need.words = words as u32,
+        Operand::AllocList(ref pairs) => {
+            for &(kind, n) in pairs {
+                match kind {
+                    0 => need.words = n,
+                    1 => need.floats = n,
+                    _ => need.funs = n,
+                }
+            }
+        }
+        _ => {}
+    }
+    need
+}
+
+/// How an instruction reads and writes the registers.
+#[derive(Debug, Default)]
+pub(crate) struct Effect {
+    /// The registers read.
+    pub uses: Registers,
+
+    /// The registers written.
+    pub defs: Registers,
+
+    /// The x registers at and above this number are undefined after the instruction.
+    pub x_limit: Option<u32>,
+
+    /// The stack frame is discarded.
+    pub kills_y: bool,
+
+    /// The number of y registers removed from the bottom of the frame by `trim`.
+    pub trim: u32,
+}
+impl Effect {
+    pub fn of(instruction: &Instruction, funs: &[parts::Function]) -> Self {
+        use Operand::*;
+
+        let mut effect = Effect::default();
+        let args = &instruction.args[..];
+        let name = instruction.name();
+        let n = |op: &Operand| match *op {
+            Unsigned(n) => n as u32,
+            _ => 0,
+        };
+        match (name, args) {
+            ("label" | "line" | "jump" | "int_code_end", _) => {}
+
+            // Calls
+            ("call" | "call_ext", [arity, _])
+            | ("call_last" | "call_ext_last", [arity, _, _])
+            | ("call_only" | "call_ext_only", [arity, _]) => effect.call(n(arity)),
+            ("call_fun", [arity]) => effect.call(n(arity) + 1),
+            ("call_fun2", [_, arity, fun]) => {
+                effect.call(n(arity));
+                effect.read(fun);
+            }
+            ("apply", [arity]) | ("apply_last", [arity, _]) => effect.call(n(arity) + 2),
+            ("make_fun2", [index]) => {
+                let num_free = funs.get(n(index) as usize).map_or(0, |f| f.num_free);
+                effect.call(num_free);
+            }
+            ("return", _) => effect.read_x(0..1),
+            ("func_info", [_, _, arity]) => effect.read_x(0..n(arity)),
+
+            // Stack frames and heap
+            ("allocate" | "allocate_zero", [_, live])
+            | ("allocate_heap" | "allocate_heap_zero", [_, _, live]) => {
+                effect.live(n(live));
+                effect.kills_y = true;
+            }
+            ("test_heap", [_, live]) => effect.live(n(live)),
+            ("deallocate", _) => effect.kills_y = true,
+            ("trim", [remove, _]) => effect.trim = n(remove),
+            ("init" | "kill", [y]) => effect.write(y),
+            ("init_yregs", [List(ys)]) => ys.iter().for_each(|y| effect.write(y)),
+
+            // Exceptions and receiving messages
+            ("try" | "catch", [y, _]) | ("try_end", [y]) => effect.write(y),
+            ("catch_end", [y]) => {
+                effect.read(y);
+                effect.write(&X(0));
+            }
+            ("try_case", [y]) => {
+                effect.read(y);
+                effect.x_limit = Some(0);
+                (0..3).for_each(|x| effect.write(&X(x)));
+            }
+            ("build_stacktrace", _) => {
+                effect.read_x(0..1);
+                effect.write(&X(0));
+            }
+            ("raw_raise", _) => effect.read_x(0..3),
+            ("loop_rec", [_, dst]) => effect.write(dst),
+            ("wait" | "wait_timeout", _) => {
+                args.iter().for_each(|a| effect.read(a));
+                effect.x_limit = Some(0);
+            }
+            ("send", _) => {
+                effect.read_x(0..2);
+                effect.write(&X(0));
+            }
+
+            // Instructions writing registers
+            ("get_list", [src, head, tail]) => {
+                effect.read(src);
+                effect.write(head);
+                effect.write(tail);
+            }
+            ("swap", [a, b]) => {
+                effect.read(a);
+                effect.read(b);
+                effect.write(a);
+                effect.write(b);
+            }
+            ("get_map_elements", [_, src, List(pairs)]) => {
+                effect.read(src);
+                for pair in pairs.chunks(2) {
+                    effect.read(&pair[0]);
+                    if let Some(dst) = pair.get(1) {
+                        effect.write(dst);
+                    }
+                }
+            }
+            ("put_tuple2", [dst, List(elements)]) => {
+                elements.iter().for_each(|e| effect.read(e));
+                effect.write(dst);
+            }
+            ("make_fun3", [_, dst, List(free)]) => {
+                free.iter().for_each(|e| effect.read(e));
+                effect.write(dst);
+            }
+            ("bs_create_bin", [_, _, live, _, dst, List(segments)]) => {
+                segments.iter().for_each(|e| effect.read(e));
+                effect.live(n(live));
+                effect.write(dst);
+            }
+            ("update_record", [_, _, src, dst, List(updates)]) => {
+                effect.read(src);
+                updates.iter().for_each(|e| effect.read(e));
+                effect.write(dst);
+            }
+            ("bs_get_position" | "bs_get_tail", [ctx, dst, live]) => {
+                effect.read(ctx);
+                effect.live(n(live));
+                effect.write(dst);
+            }
+            ("recv_marker_reserve", [dst]) => effect.write(dst),
+            _ => {
+                let dst = destination(name, args.len());
+                for (i, arg) in args.iter().enumerate() {
+                    if Some(i) != dst {
+                        effect.read(arg);
+                    }
+                }
+                if let Some(live) = live_operand(name) {
+                    effect.live(n(&args[live]));
+                }
+                if let Some(dst) = dst {
+                    effect.write(&args[dst]);
+                }
+            }
+        }
+        effect
+    }
+
+    fn call(&mut self, arity: u32) {
+        self.read_x(0..arity);
+        self.x_limit = Some(0);
+        self.write(&Operand::X(0));
+    }
+
+    fn live(&mut self, live: u32) {
+        self.read_x(0..live);
+        self.x_limit = Some(live);
+    }
+
+    fn read_x(&mut self, range: core::ops::Range<u32>) {
+        self.uses.x.extend(range);
+    }
+
+    fn read(&mut self, operand: &Operand) {
+        collect(operand, &mut self.uses);
+    }
+
+    fn write(&mut self, operand: &Operand) {
+        collect(operand, &mut self.defs);
+    }
+
+    /// Returns the registers live before the instruction from the ones live after it.
+    fn transfer(&self, live_out: &Registers) -> Registers {
+        let mut live = live_out.clone();
+        for x in &self.defs.x {
+            live.x.remove(x);
+        }
+        for y in &self.defs.y {
+            live.y.remove(y);
+        }
+        if let Some(limit) = self.x_limit {
+            live.x.retain(|&x| x < limit);
+        }
+        if self.kills_y {
+            live.y.clear();
+        }
+        if self.trim > 0 {
+            live.y = live.y.iter().map(|&y| y + self.trim).collect();
+        }
+        live.union(&self.uses);
+        live
+    }
+}
+
+fn collect(operand: &Operand, registers: &mut Registers) {
+    match *operand {
+        Operand::X(x) => {
+            registers.x.insert(x);
+        }
+        Operand::Y(y) => {
+            registers.y.insert(y);
+        }
+        Operand::TypedRegister(ref register, _) => collect(register, registers),
+        Operand::List(ref elements) => elements.iter().for_each(|e| collect(e, registers)),
+        _ => {}
+    }
+}
+
+/// Returns the index of the operand written by the instruction, if any.
+fn destination(name: &str, arity: usize) -> Option<usize> {
+    let last = arity.checked_sub(1);
+    match name {
+        "move" | "fmove" | "fconv" | "get_hd" | "get_tl" => Some(1),
+        "get_tuple_element" | "put_list" | "fnegate" => Some(2),
+        "fadd" | "fsub" | "fmul" | "fdiv" => Some(3),
+        "put_map_assoc" | "put_map_exact" => Some(2),
+        "bif0" | "bif1" | "bif2" | "gc_bif1" | "gc_bif2" | "gc_bif3" | "bs_init2"
+        | "bs_init_bits" | "bs_append" | "bs_private_append" | "bs_add" | "bs_utf8_size"
+        | "bs_utf16_size" | "bs_start_match2" | "bs_start_match3" | "bs_start_match4"
+        | "bs_get_integer2" | "bs_get_float2" | "bs_get_binary2" | "bs_get_utf8"
+        | "bs_get_utf16" | "bs_get_utf32" => last,
+        _ => None,
+    }
+}
+
+/// Returns the index of the `Live` operand of the instruction, if any.
+fn live_operand(name: &str) -> Option<usize> {
+    match name {
+        "gc_bif1" | "gc_bif2" | "gc_bif3" | "bs_start_match4" => Some(1),
+        "bs_start_match2" | "bs_start_match3" | "bs_get_integer2" | "bs_get_float2"
+        | "bs_get_binary2" | "bs_get_utf8" | "bs_get_utf16" | "bs_get_utf32" => Some(2),
+        "put_map_assoc" | "put_map_exact" | "bs_init2" | "bs_init_bits" | "bs_append" => Some(3),
+        _ => None,
+    }
+}
+
+fn is_call(name: &str) -> bool {
+    matches!(
+        name,
+        "call" | "call_ext" | "call_fun" | "call_fun2" | "apply" | "make_fun2"
+    )
+}
+
+fn liveness(
+    function: &Function,
+    cfg: &ControlFlowGraph,
+    effects: &[Effect],
+) -> (Vec<Registers>, Vec<Registers>) {
+    let len = function.instructions.len();
+    let mut live_in = vec![Registers::default(); len];
+    let mut live_out = vec![Registers::default(); len];
+
+    let mut queue = (0..cfg.blocks.len()).rev().collect::<VecDeque<_>>();
+    let mut queued = vec![true; cfg.blocks.len()];
+    while let Some(b) = queue.pop_front() {
+        queued[b] = false;
+        let block = &cfg.blocks[b];
+        let mut live = Registers::default();
+        for edge in &block.edges {
+            if let Some(target) = edge.target {
+                live.union(&live_in[cfg.blocks[target].range.start]);
+            }
+        }
+        for i in block.range.clone().rev() {
+            live_out[i] = live;
+            live = effects[i].transfer(&live_out[i]);
+            live_in[i] = live.clone();
+        }
+        for p in cfg.predecessors(b) {
+            if !queued[p] && needs_update(cfg, p, &live_in, &live_out) {
+                queued[p] = true;
+                queue.push_back(p);
+            }
+        }
+    }
+    (live_in, live_out)
+}
+
+/// Returns `true` if the registers live at the end of `block` have changed.
+fn needs_update(
+    cfg: &ControlFlowGraph,
+    block: usize,
+    live_in: &[Registers],
+    live_out: &[Registers],
+) -> bool {
+    let block = &cfg.blocks[block];
+    let mut live = Registers::default();
+    for edge in &block.edges {
+        if let Some(target) = edge.target {
+            live.union(&live_in[cfg.blocks[target].range.start]);
+        }
+    }
+    live != live_out[block.range.end - 1]
+}
+
+fn frame_sizes(function: &Function, cfg: &ControlFlowGraph) -> Vec<Option<u32>> {
+    let mut sizes = vec![None; function.instructions.len()];
+    let mut visited = vec![false; cfg.blocks.len()];
+    let mut stack = vec![(cfg.entry, None)];
+    while let Some((b, mut frame)) = stack.pop() {
+        if core::mem::replace(&mut visited[b], true) {
+            continue;
+        }
+        let block = &cfg.blocks[b];
+        for i in block.range.clone() {
+            sizes[i] = frame;
+            let instruction = &function.instructions[i];
+            frame = match (instruction.name(), &instruction.args[..]) {
+                (
+                    "allocate" | "allocate_zero" | "allocate_heap" | "allocate_heap_zero",
+                    [Operand::Unsigned(n), ..],
+                ) => Some(*n as u32),
+                ("deallocate", _) => None,
+                ("trim", [Operand::Unsigned(n), _]) => frame.map(|f| f.saturating_sub(*n as u32)),
+                _ => frame,
+            };
+        }
+        for edge in &block.edges {
+            if let Some(target) = edge.target {
+                stack.push((target, frame));
+            }
+        }
+    }
+    sizes
+}
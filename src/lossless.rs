@@ -0,0 +1,124 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufReader, BufWriter};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::beam_file::{self, Header};
+use crate::chunk::{Chunk, LosslessChunk};
+use crate::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use crate::Error;
+use crate::{BeamFile, DecodeLimits, Limit, Result};
+
+/// A BEAM file which re-encodes to exactly the bytes it was decoded from.
+///
+/// Besides the chunks (see `LosslessChunk`), this keeps the bytes
+/// following the last chunk and the bytes following the `"FOR1"` payload.
+/// Only the chunks accessed mutably are re-encoded.
+///
+/// ```
+/// use beam_file::LosslessBeamFile;
+/// use beam_file::chunk::{StandardChunk, StrTChunk};
+///
+/// let original = std::fs::read("tests/testdata/test.beam").unwrap();
+/// let mut beam = LosslessBeamFile::<StandardChunk>::from_reader(&original[..]).unwrap();
+/// beam.beam.chunk_mut::<StrTChunk>().unwrap().strings = b"patched".to_vec();
+///
+/// let mut encoded = Vec::new();
+/// beam.to_writer(&mut encoded).unwrap();
+/// assert_eq!(original.len() + 8, encoded.len());
+/// ```
+#[derive(Debug)]
+pub struct LosslessBeamFile<C> {
+    /// The BEAM file.
+    pub beam: BeamFile<LosslessChunk<C>>,
+
+    /// The bytes following the last chunk within the `"FOR1"` payload.
+    pub trailing_payload: Vec<u8>,
+
+    /// The bytes following the `"FOR1"` payload.
+    pub trailing: Vec<u8>,
+}
+impl<C: Chunk> LosslessBeamFile<C> {
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let decode = || {
+            let f = File::open(path)?;
+            Self::from_reader(BufReader::new(f))
+        };
+        decode().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_reader_with_limits(reader, &DecodeLimits::default())
+    }
+
+    /// Reads a BEAM file from `reader` within `limits`.
+    ///
+    /// `reader` is read to the end, and the bytes following the `"FOR1"` payload are included
+    /// in the file size checked against `limits.max_file_size`.
+    pub fn from_reader_with_limits<R: Read>(mut reader: R, limits: &DecodeLimits) -> Result<Self> {
+        let header = Header::from_reader(&mut reader)?;
+        header.check()?;
+        let mut payload = beam_file::read_payload(&mut reader, &header, limits)?;
+        let (chunks, end) = beam_file::decode_chunks(&payload, limits)?;
+        let trailing_payload = payload.split_off(end);
+
+        // The trailing bytes count toward `max_file_size`
+        let size = u64::from(header.payload_size) + 8;
+        let mut trailing = Vec::new();
+        let remaining = limits.max_file_size.saturating_sub(size);
+        reader
+            .take(remaining.saturating_add(1))
+            .read_to_end(&mut trailing)?;
+        limits.check(Limit::FileSize, size + trailing.len() as u64)?;
+        Ok(LosslessBeamFile {
+            beam: BeamFile { chunks },
+            trailing_payload,
+            trailing,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let f = File::create(path)?;
+        let mut writer = BufWriter::new(f);
+        self.to_seekable_writer(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut buf = Vec::new();
+        for chunk in &self.beam.chunks {
+            chunk.encode(&mut buf)?;
+        }
+        buf.extend_from_slice(&self.trailing_payload);
+
+        beam_file::write_payload(&mut writer, &buf)?;
+        writer.write_all(&self.trailing)?;
+        Ok(())
+    }
+
+    /// Writes the BEAM file to `writer` without buffering the encoded chunks.
+    pub fn to_seekable_writer<W: Write + Seek>(&self, mut writer: W) -> Result<()> {
+        beam_file::write_seekable(&mut writer, &self.beam.chunks, &self.trailing_payload)?;
+        writer.write_all(&self.trailing)?;
+        Ok(())
+    }
+}
+impl<C> From<BeamFile<C>> for LosslessBeamFile<C> {
+    fn from(beam: BeamFile<C>) -> Self {
+        let chunks = beam.chunks.into_iter().map(LosslessChunk::new).collect();
+        LosslessBeamFile {
+            beam: BeamFile { chunks },
+            trailing_payload: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+}
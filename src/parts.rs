@@ -1,4 +1,6 @@
 //! A collection of the miscellaneous parts used in a BEAM file.
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// The identifier of an atom.
 ///
@@ -16,12 +18,14 @@ pub type ExternalTermFormatBinary = Vec<u8>;
 
 /// An atom.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Atom {
     pub name: String,
 }
 
 /// An imported function.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Import {
     pub module: AtomId,
     pub function: AtomId,
@@ -30,6 +34,7 @@ pub struct Import {
 
 /// An exported function.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Export {
     pub function: AtomId,
     pub arity: Arity,
@@ -38,6 +43,7 @@ pub struct Export {
 
 /// A local function.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Local {
     pub function: AtomId,
     pub arity: Arity,
@@ -46,6 +52,7 @@ pub struct Local {
 
 /// An anonymous function.
 #[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Function {
     pub function: AtomId,
     pub arity: Arity,
@@ -54,3 +61,23 @@ pub struct Function {
     pub num_free: u32,
     pub old_uniq: u32,
 }
+
+/// An item in the "Line" chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineItem {
+    /// The index of the file which the following lines belong to.
+    File(u32),
+
+    /// A line number.
+    Line(u32),
+}
+
+/// A location in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    /// The index of the file (`0` is the source of the module, `N` is the `N`-th file name in the "Line" chunk).
+    pub file: u32,
+    pub line: u32,
+}
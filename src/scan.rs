@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::chunk::{Chunk, Id};
+use crate::{BeamFile, DecodeLimits, Error, RawBeamFile, Result};
+
+/// Loads every BEAM file under a set of directories in parallel.
+///
+/// The directories (e.g., `_build`, `ebin` or an OTP `lib/` tree) are walked recursively,
+/// and each file with the `.beam` extension is decoded on one of the worker threads.
+///
+/// ```
+/// use beam_file::Scanner;
+/// use beam_file::chunk::{AtomChunk, StandardChunk};
+///
+/// let scanner = Scanner {
+///     chunk_ids: Some(vec![*b"Atom", *b"AtU8"]),
+///     ..Scanner::default()
+/// };
+/// let entries = scanner.scan::<StandardChunk, _>(&["tests/testdata"]);
+/// assert_eq!(2, entries.len());
+/// for entry in entries {
+///     let beam = entry.result.unwrap();
+///     assert_eq!(1, beam.chunks.len());
+///     assert!(beam.chunk::<AtomChunk>().is_some());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scanner {
+    /// The identifiers of the chunks to decode.
+    ///
+    /// If `None`, all chunks are decoded.
+    /// The other chunks are skipped without being decoded.
+    pub chunk_ids: Option<Vec<Id>>,
+
+    /// The number of worker threads.
+    ///
+    /// If `0`, the available parallelism of the machine is used.
+    pub threads: usize,
+
+    /// The limits applied to each file.
+    pub limits: DecodeLimits,
+}
+impl Scanner {
+    /// Decodes the BEAM files under `roots`.
+    ///
+    /// A root may also be a BEAM file itself.
+    /// The entries of the directories which could not be read come first,
+    /// followed by those of the files in the order they are found
+    /// (the entries of a directory are visited in the order of their names).
+    pub fn scan<C, P>(&self, roots: &[P]) -> Vec<ScanEntry<C>>
+    where
+        C: Chunk + Send,
+        P: AsRef<Path>,
+    {
+        self.scan_with_progress(roots, |_| {})
+    }
+
+    /// Same as `scan`, but calls `progress` each time a file has been processed.
+    ///
+    /// `progress` is called from the worker threads.
+    pub fn scan_with_progress<C, P, F>(&self, roots: &[P], progress: F) -> Vec<ScanEntry<C>>
+    where
+        C: Chunk + Send,
+        P: AsRef<Path>,
+        F: Fn(&ScanProgress) + Sync,
+    {
+        let (paths, errors) = find_beam_files(roots);
+        let total = paths.len();
+        let done = AtomicUsize::new(0);
+        let results = parallel_map(&paths, self.threads, |path| {
+            let result = self.load(path);
+            progress(&ScanProgress {
+                path,
+                done: done.fetch_add(1, Ordering::Relaxed) + 1,
+                total,
+            });
+            result
+        });
+
+        let errors = errors.into_iter().map(|(path, e)| ScanEntry {
+            path,
+            result: Err(e),
+        });
+        let entries = paths
+            .into_iter()
+            .zip(results)
+            .map(|(path, result)| ScanEntry { path, result });
+        errors.chain(entries).collect()
+    }
+
+    fn load<C: Chunk>(&self, path: &Path) -> Result<BeamFile<C>> {
+        let mut raw = RawBeamFile::from_file_with_limits(path, &self.limits)?;
+        if let Some(ids) = &self.chunk_ids {
+            raw.chunks.retain(|c| ids.contains(&c.id));
+        }
+        raw.decode().map_err(|e| Error::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+    }
+}
+
+/// The result of loading a file in `Scanner::scan`.
+#[derive(Debug)]
+pub struct ScanEntry<C> {
+    /// The path of the file.
+    ///
+    /// If a directory could not be read, this is the path of the directory.
+    pub path: PathBuf,
+
+    /// The decoded BEAM file, or the error occurred while reading it.
+    pub result: Result<BeamFile<C>>,
+}
+
+/// The progress of `Scanner::scan_with_progress`.
+#[derive(Debug)]
+pub struct ScanProgress<'a> {
+    /// The path of the file which has just been processed.
+    pub path: &'a Path,
+
+    /// The number of processed files.
+    pub done: usize,
+
+    /// The number of files to be processed.
+    pub total: usize,
+}
+
+/// Returns the BEAM files under `roots` and the directories which could not be read.
+pub(crate) fn find_beam_files<P: AsRef<Path>>(
+    roots: &[P],
+) -> (Vec<PathBuf>, Vec<(PathBuf, Error)>) {
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    let mut visited = HashSet::new();
+    for root in roots {
+        let root = root.as_ref();
+        if root.is_dir() {
+            walk(root, &mut visited, &mut paths, &mut errors);
+        } else {
+            paths.push(root.to_path_buf());
+        }
+    }
+    (paths, errors)
+}
+
+fn walk(
+    dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    paths: &mut Vec<PathBuf>,
+    errors: &mut Vec<(PathBuf, Error)>,
+) {
+    let mut read_dir = || -> Result<Vec<PathBuf>> {
+        // Directories reachable through symbolic links (e.g., in `_build`) are visited only once
+        if !visited.insert(fs::canonicalize(dir)?) {
+            return Ok(Vec::new());
+        }
+        let mut children = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        children.sort();
+        Ok(children)
+    };
+    let children = match read_dir() {
+        Ok(children) => children,
+        Err(e) => {
+            let e = Error::InFile {
+                path: dir.to_path_buf(),
+                source: Box::new(e),
+            };
+            errors.push((dir.to_path_buf(), e));
+            return;
+        }
+    };
+    for path in children {
+        if path.is_dir() {
+            walk(&path, visited, paths, errors);
+        } else if path.extension().is_some_and(|ext| ext == "beam") {
+            paths.push(path);
+        }
+    }
+}
+
+/// Applies `f` to each item on `threads` worker threads (`0` means the available parallelism).
+pub(crate) fn parallel_map<T, U, F>(items: &[T], threads: usize, f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    thread::scope(|s| {
+        for _ in 0..threads.min(items.len()) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else {
+                    break;
+                };
+                let result = f(item);
+                results
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push((i, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
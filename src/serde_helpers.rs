@@ -0,0 +1,103 @@
+//! Helpers for `#[serde(with = "...")]` to render binaries in a readable form.
+
+/// Serializes a chunk identifier as a string (e.g., `"Atom"`).
+///
+/// An identifier which is not valid UTF-8 is serialized as `"0x"` followed by eight hexadecimal
+/// digits (e.g., `"0xff000000"`). This is unambiguous, since a string of four bytes never has ten.
+pub mod id {
+    use alloc::format;
+    use alloc::string::String;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::chunk::Id;
+
+    pub fn serialize<S: Serializer>(id: &Id, serializer: S) -> Result<S::Ok, S::Error> {
+        match core::str::from_utf8(id) {
+            Ok(s) => serializer.serialize_str(s),
+            Err(_) => serializer.serialize_str(&format!("0x{}", super::hex::encode(id))),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Id, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let hex = s
+            .strip_prefix("0x")
+            .filter(|hex| hex.len() == 8)
+            .and_then(super::hex::decode);
+        let bytes = hex.as_deref().unwrap_or(s.as_bytes());
+        if bytes.len() != 4 {
+            return Err(D::Error::invalid_length(bytes.len(), &"4 bytes"));
+        }
+        let mut id = [0; 4];
+        id.copy_from_slice(bytes);
+        Ok(id)
+    }
+}
+
+/// Serializes a binary as a hexadecimal string.
+pub mod hex {
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::fmt::Write as _;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        decode(&s).ok_or_else(|| D::Error::custom(format!("invalid hex string: {:?}", s)))
+    }
+
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut s = String::with_capacity(bytes.len() * 2);
+        for b in bytes {
+            let _ = write!(s, "{:02x}", b);
+        }
+        s
+    }
+
+    pub fn decode(s: &str) -> Option<Vec<u8>> {
+        if !s.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+            .collect()
+    }
+}
+
+/// Serializes a list of binaries as a list of hexadecimal strings.
+pub mod hex_list {
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use serde::de::Error as _;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(list: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(list.len()))?;
+        for bytes in list {
+            seq.serialize_element(&super::hex::encode(bytes))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| {
+                super::hex::decode(s)
+                    .ok_or_else(|| D::Error::custom(format!("invalid hex string: {:?}", s)))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,1110 @@
+//! Erlang terms encoded in the [External Term Format] (e.g., literals and attributes).
+//!
+//! [External Term Format]: http://erlang.org/doc/apps/erts/erl_ext_dist.html
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use libflate::zlib;
+
+use crate::chunk::aux;
+use crate::io::{Read, Write};
+use crate::io_ext::{ReadExt, WriteExt};
+use crate::{Error, Result};
+
+const VERSION: u8 = 131;
+const MAX_DEPTH: usize = 512;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const BIT_BINARY_EXT: u8 = 77;
+const COMPRESSED: u8 = 80;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const EXPORT_EXT: u8 = 113;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// An Erlang term.
+///
+/// Process identifiers, ports, references and closures are not supported
+/// since they do not appear in the chunks of a BEAM file.
+///
+/// ```
+/// use beam_file::term::Term;
+///
+/// let term = Term::Tuple(vec![Term::atom("ok"), Term::List(vec![Term::Integer(104), Term::Integer(105)])]);
+/// assert_eq!("{ok,\"hi\"}", term.to_string());
+/// assert_eq!(term, Term::decode(&term.encode().unwrap()).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Integer(i64),
+
+    /// An integer which does not fit in `i64`.
+    BigInteger {
+        negative: bool,
+
+        /// The absolute value in little-endian order.
+        magnitude: Vec<u8>,
+    },
+
+    Float(f64),
+    Atom(String),
+    Binary(Vec<u8>),
+
+    /// A bitstring whose last byte has only `bits` (`1..=7`) significant bits.
+    BitString {
+        bytes: Vec<u8>,
+        bits: u8,
+    },
+
+    Tuple(Vec<Term>),
+
+    /// A proper list (`[]` if empty).
+    List(Vec<Term>),
+
+    /// A list whose tail is not `[]`.
+    ImproperList(Vec<Term>, Box<Term>),
+
+    Map(Vec<(Term, Term)>),
+
+    /// A fun referring to an exported function (i.e., `fun Module:Function/Arity`).
+    ExternalFun {
+        module: String,
+        function: String,
+        arity: u8,
+    },
+}
+impl Term {
+    /// Makes an atom.
+    pub fn atom(name: &str) -> Self {
+        Term::Atom(String::from(name))
+    }
+
+    /// Decodes a term prefixed with the version number `131`.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        Self::from_reader(bytes)
+    }
+
+    /// Reads a term prefixed with the version number `131`.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let version = reader.read_u8()?;
+        if version != VERSION {
+            return Err(Error::InvalidTerm {
+                reason: "unknown version",
+            });
+        }
+        let tag = reader.read_u8()?;
+        if tag != COMPRESSED {
+            return decode_tagged_term(&mut reader, tag, 0);
+        }
+
+        // The whole term is compressed by `term_to_binary(Term, [compressed])`
+        let size = reader.read_u32()?;
+        let mut bytes = Vec::with_capacity(aux::capacity(size as usize));
+        zlib::Decoder::new(reader)?
+            .take(u64::from(size) + 1)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != size as usize {
+            return Err(Error::InvalidTerm {
+                reason: "uncompressed size mismatch",
+            });
+        }
+        decode_term(&mut &bytes[..], 0)
+    }
+
+    /// Encodes the term in the same way as `term_to_binary/1`.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.to_writer(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Writes the term prefixed with the version number `131`.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_u8(VERSION)?;
+        encode_term(self, &mut writer)
+    }
+}
+impl From<u32> for Term {
+    fn from(n: u32) -> Self {
+        Term::Integer(i64::from(n))
+    }
+}
+
+impl fmt::Display for Term {
+    /// Formats the term in the same way as `io:format("~p", [Term])` without line breaks.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Term::Integer(n) => write!(f, "{}", n),
+            Term::BigInteger {
+                negative,
+                ref magnitude,
+            } => fmt_big_integer(f, negative, magnitude),
+            Term::Float(n) => fmt_float(f, n),
+            Term::Atom(ref name) => fmt_atom(f, name),
+            Term::Binary(ref bytes) => fmt_bitstring(f, bytes, 8),
+            Term::BitString { ref bytes, bits } => fmt_bitstring(f, bytes, bits),
+            Term::Tuple(ref elements) => {
+                f.write_str("{")?;
+                fmt_elements(f, elements)?;
+                f.write_str("}")
+            }
+            Term::List(ref elements) => match printable_string(elements) {
+                Some(s) => fmt_quoted(f, &s, '"'),
+                None => {
+                    f.write_str("[")?;
+                    fmt_elements(f, elements)?;
+                    f.write_str("]")
+                }
+            },
+            Term::ImproperList(ref elements, ref tail) => {
+                f.write_str("[")?;
+                fmt_elements(f, elements)?;
+                write!(f, "|{}]", tail)
+            }
+            Term::Map(ref pairs) => {
+                f.write_str("#{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{} => {}", key, value)?;
+                }
+                f.write_str("}")
+            }
+            Term::ExternalFun {
+                ref module,
+                ref function,
+                arity,
+            } => {
+                f.write_str("fun ")?;
+                fmt_atom(f, module)?;
+                f.write_str(":")?;
+                fmt_atom(f, function)?;
+                write!(f, "/{}", arity)
+            }
+        }
+    }
+}
+
+/// Formats `name` as an atom, quoting it if necessary.
+pub(crate) fn fmt_atom(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    if needs_quote(name) {
+        fmt_quoted(f, name, '\'')
+    } else {
+        f.write_str(name)
+    }
+}
+
+fn needs_quote(name: &str) -> bool {
+    const RESERVED_WORDS: &[&str] = &[
+        "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+        "catch", "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or",
+        "orelse", "receive", "rem", "try", "when", "xor",
+    ];
+    let mut chars = name.chars();
+    !chars.next().is_some_and(is_lower)
+        || !chars.all(is_name_char)
+        || RESERVED_WORDS.contains(&name)
+}
+
+fn is_lower(c: char) -> bool {
+    c.is_ascii_lowercase() || ('ß'..='ÿ').contains(&c) && c != '÷'
+}
+
+fn is_name_char(c: char) -> bool {
+    is_lower(c)
+        || c.is_ascii_uppercase()
+        || ('À'..='Þ').contains(&c) && c != '×'
+        || c.is_ascii_digit()
+        || c == '_'
+        || c == '@'
+}
+
+/// Formats `s` enclosed in `quote` with the escape sequences of `io_lib`.
+pub(crate) fn fmt_quoted(f: &mut fmt::Formatter, s: &str, quote: char) -> fmt::Result {
+    use fmt::Write as _;
+
+    f.write_char(quote)?;
+    for c in s.chars() {
+        match c {
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\u{b}' => f.write_str("\\v")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '\u{c}' => f.write_str("\\f")?,
+            '\u{1b}' => f.write_str("\\e")?,
+            '\u{7f}' => f.write_str("\\d")?,
+            '\\' => f.write_str("\\\\")?,
+            c if c == quote => write!(f, "\\{}", c)?,
+            ' '..='~' | '\u{a0}'..='\u{ff}' => f.write_char(c)?,
+            c if u32::from(c) < 0xa0 => write!(f, "\\{:03o}", u32::from(c))?,
+            c => write!(f, "\\x{{{:X}}}", u32::from(c))?,
+        }
+    }
+    f.write_char(quote)
+}
+
+fn fmt_elements(f: &mut fmt::Formatter, elements: &[Term]) -> fmt::Result {
+    for (i, e) in elements.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write!(f, "{}", e)?;
+    }
+    Ok(())
+}
+
+/// Returns the string if `elements` is a non-empty list of printable Latin-1 characters.
+fn printable_string(elements: &[Term]) -> Option<String> {
+    if elements.is_empty() {
+        return None;
+    }
+    elements
+        .iter()
+        .map(|e| match *e {
+            Term::Integer(n) => u8::try_from(n)
+                .ok()
+                .filter(|&b| is_printable(b))
+                .map(char::from),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_printable(b: u8) -> bool {
+    matches!(b, 32..=126 | 160..=255 | b'\n' | b'\r' | b'\t' | 11 | 8 | 12 | 27)
+}
+
+fn fmt_bitstring(f: &mut fmt::Formatter, bytes: &[u8], bits: u8) -> fmt::Result {
+    let (whole, last) = if bits == 8 {
+        (bytes, None)
+    } else {
+        match bytes.split_last() {
+            Some((&last, whole)) => (whole, Some(last >> (8 - bits))),
+            None => (bytes, None),
+        }
+    };
+    f.write_str("<<")?;
+    if !whole.is_empty() && whole.iter().all(|&b| is_printable(b)) {
+        let s = whole.iter().map(|&b| char::from(b)).collect::<String>();
+        fmt_quoted(f, &s, '"')?;
+    } else {
+        for (i, b) in whole.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", b)?;
+        }
+    }
+    if let Some(last) = last {
+        if !whole.is_empty() {
+            f.write_str(",")?;
+        }
+        write!(f, "{}:{}", last, bits)?;
+    }
+    f.write_str(">>")
+}
+
+fn fmt_big_integer(f: &mut fmt::Formatter, negative: bool, magnitude: &[u8]) -> fmt::Result {
+    // Repeatedly divides the magnitude by 10 to collect the decimal digits
+    let mut n = magnitude.to_vec();
+    let mut digits = Vec::new();
+    while n.iter().any(|&b| b != 0) {
+        let mut remainder = 0u16;
+        for b in n.iter_mut().rev() {
+            let v = (remainder << 8) | u16::from(*b);
+            *b = (v / 10) as u8;
+            remainder = v % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    if negative {
+        f.write_str("-")?;
+    }
+    for &d in digits.iter().rev() {
+        write!(f, "{}", char::from(d))?;
+    }
+    Ok(())
+}
+
+/// Formats `n` with the fewest digits in the same way as `io_lib_format:fwrite_g/1`.
+fn fmt_float(f: &mut fmt::Formatter, n: f64) -> fmt::Result {
+    if !n.is_finite() {
+        // Not representable in Erlang (the decoder rejects them)
+        return write!(f, "{}", n);
+    }
+    if n.is_sign_negative() {
+        f.write_str("-")?;
+    }
+    // `{:e}` gives the shortest digits which round-trip (e.g., "1.25e-3")
+    let s = alloc::format!("{:e}", n.abs());
+    let (mantissa, exponent) = s.split_once('e').unwrap_or((&s, "0"));
+    let digits = mantissa.replace('.', "");
+    let exponent = exponent.parse::<i32>().unwrap_or(0);
+
+    // The value is `0.<digits> * 10^place`
+    let place = exponent + 1;
+    let len = digits.len() as i32;
+    if place > 0 && place < len {
+        let (int, frac) = digits.split_at(place as usize);
+        return write!(f, "{}.{}", int, frac);
+    }
+    if place == 0 {
+        return write!(f, "0.{}", digits);
+    }
+    let exp_len = alloc::format!("{}", place - 1).len() as i32;
+    let exp_cost = exp_len + 1 + if len == 1 { 2 } else { 1 };
+    if place < 0 && 2 - place <= exp_cost {
+        write!(f, "0.")?;
+        for _ in 0..-place {
+            f.write_str("0")?;
+        }
+        f.write_str(&digits)
+    } else if place > 0 && place - len + 2 <= exp_cost {
+        f.write_str(&digits)?;
+        for _ in 0..place - len {
+            f.write_str("0")?;
+        }
+        f.write_str(".0")
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let rest = if rest.is_empty() { "0" } else { rest };
+        write!(f, "{}.{}e{}", first, rest, place - 1)
+    }
+}
+
+/// Rejects infinities and NaNs, which Erlang cannot represent.
+fn float(n: f64) -> Result<Term> {
+    if !n.is_finite() {
+        return Err(Error::InvalidTerm {
+            reason: "bad float",
+        });
+    }
+    Ok(Term::Float(n))
+}
+
+fn decode_term<R: Read>(reader: &mut R, depth: usize) -> Result<Term> {
+    if depth > MAX_DEPTH {
+        return Err(Error::InvalidTerm {
+            reason: "too deeply nested",
+        });
+    }
+    let tag = reader.read_u8()?;
+    decode_tagged_term(reader, tag, depth)
+}
+
+fn decode_tagged_term<R: Read>(reader: &mut R, tag: u8, depth: usize) -> Result<Term> {
+    let term = match tag {
+        SMALL_INTEGER_EXT => Term::Integer(i64::from(reader.read_u8()?)),
+        INTEGER_EXT => Term::Integer(i64::from(reader.read_u32()? as i32)),
+        SMALL_BIG_EXT | LARGE_BIG_EXT => {
+            let len = if tag == SMALL_BIG_EXT {
+                u32::from(reader.read_u8()?)
+            } else {
+                reader.read_u32()?
+            };
+            let negative = reader.read_u8()? != 0;
+            let magnitude = aux::read_bytes(&mut *reader, len as usize)?;
+            big_integer(negative, magnitude)
+        }
+        NEW_FLOAT_EXT => float(f64::from_bits(reader.read_u64()?))?,
+        FLOAT_EXT => {
+            let bytes = aux::read_bytes(&mut *reader, 31)?;
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            let n = core::str::from_utf8(&bytes[..end])?
+                .trim()
+                .parse()
+                .map_err(|_| Error::InvalidTerm {
+                    reason: "bad float",
+                })?;
+            float(n)?
+        }
+        ATOM_EXT | SMALL_ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_UTF8_EXT => {
+            Term::Atom(decode_atom_body(reader, tag)?)
+        }
+        SMALL_TUPLE_EXT | LARGE_TUPLE_EXT => {
+            let arity = if tag == SMALL_TUPLE_EXT {
+                u32::from(reader.read_u8()?)
+            } else {
+                reader.read_u32()?
+            };
+            let elements = decode_terms(reader, arity, depth)?;
+            Term::Tuple(elements)
+        }
+        NIL_EXT => Term::List(Vec::new()),
+        STRING_EXT => {
+            let len = reader.read_u16()?;
+            let bytes = aux::read_bytes(&mut *reader, usize::from(len))?;
+            Term::List(
+                bytes
+                    .into_iter()
+                    .map(|b| Term::Integer(i64::from(b)))
+                    .collect(),
+            )
+        }
+        LIST_EXT => {
+            let len = reader.read_u32()?;
+            let elements = decode_terms(reader, len, depth)?;
+            match decode_term(reader, depth + 1)? {
+                Term::List(ref tail) if tail.is_empty() => Term::List(elements),
+                tail => Term::ImproperList(elements, Box::new(tail)),
+            }
+        }
+        BINARY_EXT => {
+            let len = reader.read_u32()?;
+            Term::Binary(aux::read_bytes(&mut *reader, len as usize)?)
+        }
+        BIT_BINARY_EXT => {
+            let len = reader.read_u32()?;
+            let bits = reader.read_u8()?;
+            let bytes = aux::read_bytes(&mut *reader, len as usize)?;
+            match bits {
+                8 => Term::Binary(bytes),
+                1..=7 if !bytes.is_empty() => Term::BitString { bytes, bits },
+                _ => {
+                    return Err(Error::InvalidTerm {
+                        reason: "bad number of bits",
+                    })
+                }
+            }
+        }
+        MAP_EXT => {
+            let arity = reader.read_u32()?;
+            let mut pairs = Vec::with_capacity(aux::capacity(arity as usize));
+            for _ in 0..arity {
+                let key = decode_term(reader, depth + 1)?;
+                let value = decode_term(reader, depth + 1)?;
+                pairs.push((key, value));
+            }
+            Term::Map(pairs)
+        }
+        EXPORT_EXT => {
+            let mut atom = || match decode_term(reader, depth + 1)? {
+                Term::Atom(name) => Ok(name),
+                _ => Err(Error::InvalidTerm {
+                    reason: "non-atom in an external fun",
+                }),
+            };
+            let module = atom()?;
+            let function = atom()?;
+            let arity = match decode_term(reader, depth + 1)? {
+                Term::Integer(n) => u8::try_from(n).ok(),
+                _ => None,
+            }
+            .ok_or(Error::InvalidTerm {
+                reason: "bad arity in an external fun",
+            })?;
+            Term::ExternalFun {
+                module,
+                function,
+                arity,
+            }
+        }
+        _ => return Err(Error::UnsupportedTerm { tag }),
+    };
+    Ok(term)
+}
+
+fn decode_terms<R: Read>(reader: &mut R, count: u32, depth: usize) -> Result<Vec<Term>> {
+    let mut terms = Vec::with_capacity(aux::capacity(count as usize));
+    for _ in 0..count {
+        terms.push(decode_term(reader, depth + 1)?);
+    }
+    Ok(terms)
+}
+
+fn decode_atom_body<R: Read>(reader: &mut R, tag: u8) -> Result<String> {
+    let len = match tag {
+        SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => usize::from(reader.read_u8()?),
+        _ => usize::from(reader.read_u16()?),
+    };
+    let bytes = aux::read_bytes(&mut *reader, len)?;
+    if tag == ATOM_EXT || tag == SMALL_ATOM_EXT {
+        // Latin-1
+        Ok(bytes.into_iter().map(char::from).collect())
+    } else {
+        Ok(String::from_utf8(bytes).map_err(|e| e.utf8_error())?)
+    }
+}
+
+fn big_integer(negative: bool, mut magnitude: Vec<u8>) -> Term {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    if magnitude.len() <= 8 {
+        let mut buf = [0; 8];
+        buf[..magnitude.len()].copy_from_slice(&magnitude);
+        let n = u64::from_le_bytes(buf);
+        if negative && n <= 1 << 63 {
+            return Term::Integer((n as i64).wrapping_neg());
+        } else if !negative && n < 1 << 63 {
+            return Term::Integer(n as i64);
+        }
+    }
+    Term::BigInteger {
+        negative,
+        magnitude,
+    }
+}
+
+fn encode_term<W: Write>(term: &Term, writer: &mut W) -> Result<()> {
+    match *term {
+        Term::Integer(n) => {
+            if let Ok(n) = u8::try_from(n) {
+                writer.write_u8(SMALL_INTEGER_EXT)?;
+                writer.write_u8(n)?;
+            } else if let Ok(n) = i32::try_from(n) {
+                writer.write_u8(INTEGER_EXT)?;
+                writer.write_u32(n as u32)?;
+            } else {
+                let magnitude = n.unsigned_abs().to_le_bytes();
+                let len = magnitude.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+                writer.write_u8(SMALL_BIG_EXT)?;
+                writer.write_u8(len as u8)?;
+                writer.write_u8(u8::from(n < 0))?;
+                writer.write_all(&magnitude[..len])?;
+            }
+        }
+        Term::BigInteger {
+            negative,
+            ref magnitude,
+        } => {
+            if let Ok(len) = u8::try_from(magnitude.len()) {
+                writer.write_u8(SMALL_BIG_EXT)?;
+                writer.write_u8(len)?;
+            } else {
+                writer.write_u8(LARGE_BIG_EXT)?;
+                writer.write_u32(aux::to_u32("integer", magnitude.len())?)?;
+            }
+            writer.write_u8(u8::from(negative))?;
+            writer.write_all(magnitude)?;
+        }
+        Term::Float(n) => {
+            writer.write_u8(NEW_FLOAT_EXT)?;
+            writer.write_u64(n.to_bits())?;
+        }
+        Term::Atom(ref name) => encode_atom(name, writer)?,
+        Term::Binary(ref bytes) => {
+            writer.write_u8(BINARY_EXT)?;
+            writer.write_u32(aux::to_u32("binary", bytes.len())?)?;
+            writer.write_all(bytes)?;
+        }
+        Term::BitString { ref bytes, bits } => {
+            writer.write_u8(BIT_BINARY_EXT)?;
+            writer.write_u32(aux::to_u32("bitstring", bytes.len())?)?;
+            writer.write_u8(bits)?;
+            writer.write_all(bytes)?;
+        }
+        Term::Tuple(ref elements) => {
+            if let Ok(arity) = u8::try_from(elements.len()) {
+                writer.write_u8(SMALL_TUPLE_EXT)?;
+                writer.write_u8(arity)?;
+            } else {
+                writer.write_u8(LARGE_TUPLE_EXT)?;
+                writer.write_u32(aux::to_u32("tuple", elements.len())?)?;
+            }
+            for e in elements {
+                encode_term(e, writer)?;
+            }
+        }
+        Term::List(ref elements) if elements.is_empty() => writer.write_u8(NIL_EXT)?,
+        Term::List(ref elements) => {
+            let bytes = elements
+                .iter()
+                .map(|e| match *e {
+                    Term::Integer(n) => u8::try_from(n).ok(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            match bytes {
+                Some(bytes) if bytes.len() <= usize::from(u16::MAX) => {
+                    writer.write_u8(STRING_EXT)?;
+                    writer.write_u16(bytes.len() as u16)?;
+                    writer.write_all(&bytes)?;
+                }
+                _ => encode_list(elements, &Term::List(Vec::new()), writer)?,
+            }
+        }
+        Term::ImproperList(ref elements, ref tail) => encode_list(elements, tail, writer)?,
+        Term::Map(ref pairs) => {
+            writer.write_u8(MAP_EXT)?;
+            writer.write_u32(aux::to_u32("map", pairs.len())?)?;
+            for (key, value) in pairs {
+                encode_term(key, writer)?;
+                encode_term(value, writer)?;
+            }
+        }
+        Term::ExternalFun {
+            ref module,
+            ref function,
+            arity,
+        } => {
+            writer.write_u8(EXPORT_EXT)?;
+            encode_atom(module, writer)?;
+            encode_atom(function, writer)?;
+            writer.write_u8(SMALL_INTEGER_EXT)?;
+            writer.write_u8(arity)?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_list<W: Write>(elements: &[Term], tail: &Term, writer: &mut W) -> Result<()> {
+    writer.write_u8(LIST_EXT)?;
+    writer.write_u32(aux::to_u32("list", elements.len())?)?;
+    for e in elements {
+        encode_term(e, writer)?;
+    }
+    encode_term(tail, writer)
+}
+
+fn encode_atom<W: Write>(name: &str, writer: &mut W) -> Result<()> {
+    if let Ok(len) = u8::try_from(name.len()) {
+        writer.write_u8(SMALL_ATOM_UTF8_EXT)?;
+        writer.write_u8(len)?;
+    } else {
+        let len = u16::try_from(name.len()).map_err(|_| Error::TooLarge {
+            what: "atom",
+            size: name.len() as u64,
+        })?;
+        writer.write_u8(ATOM_UTF8_EXT)?;
+        writer.write_u16(len)?;
+    }
+    writer.write_all(name.as_bytes())?;
+    Ok(())
+}
+
+impl core::str::FromStr for Term {
+    type Err = Error;
+
+    /// Parses a term written in the Erlang syntax (e.g., `{ok,"hi"}`).
+    ///
+    /// ```
+    /// use beam_file::term::Term;
+    ///
+    /// let term = "{ok, [1 | foo], <<\"bin\">>, #{'K' => $a}}".parse::<Term>().unwrap();
+    /// assert_eq!("{ok,[1|foo],<<\"bin\">>,#{'K' => 97}}", term.to_string());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let term = parser.term(0)?;
+        parser.skip_whitespace();
+        if parser.peek().is_some() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(term)
+    }
+}
+
+/// Parses the terms each terminated by `.` (e.g., the contents of a `.S` file),
+/// returning them with their line numbers.
+pub(crate) fn parse_forms(text: &str) -> Result<Vec<(usize, Term)>> {
+    let mut parser = Parser::new(text);
+    let mut forms = Vec::new();
+    loop {
+        parser.skip_whitespace();
+        if parser.peek().is_none() {
+            return Ok(forms);
+        }
+        let line = parser.line;
+        let term = parser.term(0)?;
+        parser.expect('.')?;
+        if parser
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && c != '%')
+        {
+            return Err(parser.error("no whitespace after '.'"));
+        }
+        forms.push((line, term));
+    }
+}
+
+struct Parser<'a> {
+    chars: core::iter::Peekable<core::str::Chars<'a>>,
+    line: usize,
+}
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            chars: text.chars().peekable(),
+            line: 1,
+        }
+    }
+
+    fn error(&self, reason: &'static str) -> Error {
+        Error::InvalidSyntax {
+            line: self.line,
+            reason,
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Result<char> {
+        let c = self.chars.next().ok_or(Error::InvalidSyntax {
+            line: self.line,
+            reason: "unexpected end of input",
+        })?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Ok(c)
+    }
+
+    fn next_if(&mut self, f: impl Fn(char) -> bool) -> Option<char> {
+        let c = self.chars.next_if(|&c| f(c))?;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            if self.next_if(char::is_whitespace).is_some() {
+                continue;
+            }
+            if self.next_if(|c| c == '%').is_none() {
+                return;
+            }
+            while self.next_if(|c| c != '\n').is_some() {}
+        }
+    }
+
+    /// Skips whitespace and consumes `c` if it comes next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        self.next_if(|x| x == c).is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn term(&mut self, depth: usize) -> Result<Term> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+        self.skip_whitespace();
+        let c = self.peek().ok_or(self.error("unexpected end of input"))?;
+        let term = match c {
+            '{' => {
+                self.next()?;
+                Term::Tuple(self.elements('}', depth)?)
+            }
+            '[' => {
+                self.next()?;
+                self.list(depth)?
+            }
+            '<' => {
+                self.next()?;
+                self.expect('<')?;
+                self.binary()?
+            }
+            '#' => {
+                self.next()?;
+                self.expect('{')?;
+                self.map(depth)?
+            }
+            '"' => {
+                let mut s = Vec::new();
+                while self.eat('"') {
+                    s.extend(self.quoted('"')?);
+                }
+                Term::List(
+                    s.into_iter()
+                        .map(|c| Term::Integer(i64::from(u32::from(c))))
+                        .collect(),
+                )
+            }
+            '\'' => {
+                self.next()?;
+                Term::Atom(self.quoted('\'')?.into_iter().collect())
+            }
+            '$' => {
+                self.next()?;
+                let c = match self.next()? {
+                    '\\' => self.escape()?,
+                    c => c,
+                };
+                Term::Integer(i64::from(u32::from(c)))
+            }
+            '-' | '0'..='9' => self.number()?,
+            c if is_lower(c) => {
+                let name = self.name();
+                if name == "fun" {
+                    self.external_fun()?
+                } else {
+                    Term::Atom(name)
+                }
+            }
+            _ => return Err(self.error("unexpected character")),
+        };
+        Ok(term)
+    }
+
+    /// Parses the comma-separated terms up to `close`.
+    fn elements(&mut self, close: char, depth: usize) -> Result<Vec<Term>> {
+        let mut elements = Vec::new();
+        if self.eat(close) {
+            return Ok(elements);
+        }
+        loop {
+            elements.push(self.term(depth + 1)?);
+            if self.eat(close) {
+                return Ok(elements);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn list(&mut self, depth: usize) -> Result<Term> {
+        let mut elements = Vec::new();
+        if self.eat(']') {
+            return Ok(Term::List(elements));
+        }
+        loop {
+            elements.push(self.term(depth + 1)?);
+            if self.eat(']') {
+                return Ok(Term::List(elements));
+            }
+            if self.eat('|') {
+                let tail = self.term(depth + 1)?;
+                self.expect(']')?;
+                return Ok(match tail {
+                    Term::List(rest) => {
+                        elements.extend(rest);
+                        Term::List(elements)
+                    }
+                    Term::ImproperList(rest, tail) => {
+                        elements.extend(rest);
+                        Term::ImproperList(elements, tail)
+                    }
+                    tail => Term::ImproperList(elements, Box::new(tail)),
+                });
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Parses the segments of a binary after `<<`, which are strings or bytes.
+    fn binary(&mut self) -> Result<Term> {
+        let mut bytes = Vec::new();
+        if !self.eat('>') {
+            loop {
+                match self.term(0)? {
+                    Term::Integer(n) => {
+                        bytes.push(u8::try_from(n).map_err(|_| self.error("byte out of range"))?)
+                    }
+                    Term::List(s) => {
+                        for c in s {
+                            match c {
+                                Term::Integer(n) if n <= 0xFF => bytes.push(n as u8),
+                                _ => return Err(self.error("non-latin-1 string in binary")),
+                            }
+                        }
+                    }
+                    _ => return Err(self.error("unsupported binary segment")),
+                }
+                if self.eat('>') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+        self.expect('>')?;
+        Ok(Term::Binary(bytes))
+    }
+
+    fn map(&mut self, depth: usize) -> Result<Term> {
+        let mut pairs = Vec::new();
+        if self.eat('}') {
+            return Ok(Term::Map(pairs));
+        }
+        loop {
+            let key = self.term(depth + 1)?;
+            self.expect('=')?;
+            if self.next_if(|c| c == '>').is_none() {
+                return Err(self.error("expected '=>'"));
+            }
+            pairs.push((key, self.term(depth + 1)?));
+            if self.eat('}') {
+                return Ok(Term::Map(pairs));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Parses `Module:Function/Arity` after `fun`.
+    fn external_fun(&mut self) -> Result<Term> {
+        let atom = |parser: &mut Self| match parser.term(0)? {
+            Term::Atom(name) => Ok(name),
+            _ => Err(parser.error("expected an atom")),
+        };
+        let module = atom(self)?;
+        self.expect(':')?;
+        let function = atom(self)?;
+        self.expect('/')?;
+        let arity = match self.term(0)? {
+            Term::Integer(n) => u8::try_from(n).map_err(|_| self.error("arity out of range"))?,
+            _ => return Err(self.error("expected an arity")),
+        };
+        Ok(Term::ExternalFun {
+            module,
+            function,
+            arity,
+        })
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.next_if(is_name_char) {
+            name.push(c);
+        }
+        name
+    }
+
+    /// Parses the characters up to `quote`, which has already been consumed.
+    fn quoted(&mut self, quote: char) -> Result<Vec<char>> {
+        let mut s = Vec::new();
+        loop {
+            match self.next()? {
+                c if c == quote => return Ok(s),
+                '\\' => s.push(self.escape()?),
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// Parses an escape sequence after `\`.
+    fn escape(&mut self) -> Result<char> {
+        let c = match self.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\u{b}',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'e' => '\u{1b}',
+            's' => ' ',
+            'd' => '\u{7f}',
+            c @ '0'..='7' => {
+                let mut n = c.to_digit(8).expect("octal digit");
+                for _ in 0..2 {
+                    match self.next_if(|c| c.is_digit(8)) {
+                        Some(c) => n = n * 8 + c.to_digit(8).expect("octal digit"),
+                        None => break,
+                    }
+                }
+                char::from_u32(n).expect("latin-1 character")
+            }
+            'x' => {
+                let mut n = 0u32;
+                if self.next_if(|c| c == '{').is_some() {
+                    while let Some(c) = self.next_if(|c| c.is_ascii_hexdigit()) {
+                        n = n
+                            .checked_mul(16)
+                            .ok_or(self.error("character out of range"))?
+                            + c.to_digit(16).expect("hex digit");
+                    }
+                    self.expect('}')?;
+                } else {
+                    for _ in 0..2 {
+                        let c = self
+                            .next_if(|c| c.is_ascii_hexdigit())
+                            .ok_or(self.error("expected a hex digit"))?;
+                        n = n * 16 + c.to_digit(16).expect("hex digit");
+                    }
+                }
+                char::from_u32(n).ok_or(self.error("character out of range"))?
+            }
+            '^' => char::from(self.next()? as u8 & 0x1F),
+            c => c,
+        };
+        Ok(c)
+    }
+
+    fn number(&mut self) -> Result<Term> {
+        let negative = self.next_if(|c| c == '-').is_some();
+        let digits = |parser: &mut Self, radix: u32| {
+            let mut s = String::new();
+            while let Some(c) = parser.next_if(|c| c.is_digit(radix) || c == '_') {
+                if c != '_' {
+                    s.push(c);
+                }
+            }
+            if s.is_empty() {
+                Err(parser.error("expected a digit"))
+            } else {
+                Ok(s)
+            }
+        };
+        let mut int = digits(self, 10)?;
+        let mut radix = 10;
+        if self.next_if(|c| c == '#').is_some() {
+            radix = int
+                .parse()
+                .ok()
+                .filter(|r| (2..=36).contains(r))
+                .ok_or(self.error("invalid radix"))?;
+            int = digits(self, radix)?;
+        } else if self
+            .chars
+            .clone()
+            .nth(1)
+            .is_some_and(|c| c.is_ascii_digit())
+            && self.next_if(|c| c == '.').is_some()
+        {
+            let mut s = alloc::format!("{}.{}", int, digits(self, 10)?);
+            if let Some(e) = self.next_if(|c| c == 'e' || c == 'E') {
+                s.push(e);
+                if let Some(sign) = self.next_if(|c| c == '-' || c == '+') {
+                    s.push(sign);
+                }
+                s.push_str(&digits(self, 10)?);
+            }
+            let n = s.parse::<f64>().map_err(|_| self.error("invalid float"))?;
+            return Ok(Term::Float(if negative { -n } else { n }));
+        }
+
+        // Accumulates the digits into a little-endian magnitude
+        let mut magnitude = Vec::<u8>::new();
+        for c in int.chars() {
+            let mut carry = c.to_digit(radix).expect("digit");
+            for b in magnitude.iter_mut() {
+                let v = u32::from(*b) * radix + carry;
+                *b = v as u8;
+                carry = v >> 8;
+            }
+            while carry > 0 {
+                magnitude.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+        Ok(big_integer(negative, magnitude))
+    }
+}
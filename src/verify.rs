@@ -0,0 +1,432 @@
+//! A verifier of the `"Code"` chunk in the manner of `beam_validator`.
+//!
+//! Each function is checked for:
+//! - the indices of the atoms, the literals, the imports and the funs in the operands
+//! - the labels in the operands, and the arities of the calls
+//! - the initialization of the registers (with the rules in `liveness`)
+//! - the allocation and the deallocation of the stack frame
+//! - the heap reserved by `test_heap` or `allocate_heap*` before building terms
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::cfg::{ControlFlowGraph, EdgeKind};
+use crate::chunk::{AtomChunk, Chunk, FunTChunk, ImpTChunk, LitTChunk};
+use crate::code::{self, Function, Instruction, Operand};
+use crate::liveness::{heap_need, Effect};
+use crate::parts;
+use crate::{BeamFile, Result};
+
+/// A violation found by `check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The name of the function.
+    pub function: String,
+    pub arity: u32,
+
+    /// The offset of the instruction in `CodeChunk::bytecode`.
+    pub offset: usize,
+    pub reason: &'static str,
+}
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}: offset - {}, reason - {}",
+            self.function, self.arity, self.offset, self.reason
+        )
+    }
+}
+
+/// Verifies the functions in the `"Code"` chunk of `beam`.
+///
+/// Returns the violations ordered by the function and the offset.
+/// The `"Atom"` (or `"AtU8"`) and `"Code"` chunks are required.
+///
+/// ```
+/// use beam_file::StandardBeamFile;
+/// use beam_file::verify;
+///
+/// let beam = StandardBeamFile::from_file("tests/testdata/test.beam").unwrap();
+/// assert_eq!(Vec::<verify::Violation>::new(), verify::check(&beam).unwrap());
+/// ```
+pub fn check<C: Chunk>(beam: &BeamFile<C>) -> Result<Vec<Violation>> {
+    let functions = code::functions(beam)?;
+    let tables = Tables {
+        atom_count: beam.require_chunk::<AtomChunk>()?.atoms.len(),
+        literal_count: beam
+            .decode_chunk::<LitTChunk>()?
+            .map_or(0, |c| c.literals.len()),
+        imports: beam
+            .decode_chunk::<ImpTChunk>()?
+            .map(|c| c.imports)
+            .unwrap_or_default(),
+        funs: beam
+            .decode_chunk::<FunTChunk>()?
+            .map(|c| c.functions)
+            .unwrap_or_default(),
+        entries: functions.iter().map(|f| (f.entry, f.arity)).collect(),
+    };
+
+    let mut violations = Vec::new();
+    for function in &functions {
+        let mut found = BTreeSet::new();
+        let labels_ok = check_operands(function, &tables, &mut found);
+        if labels_ok {
+            if let Ok(cfg) = ControlFlowGraph::new(function) {
+                check_flow(function, &cfg, &tables.funs, &mut found);
+            }
+        }
+        violations.extend(found.into_iter().map(|(offset, reason)| Violation {
+            function: function.name.clone(),
+            arity: function.arity,
+            offset,
+            reason,
+        }));
+    }
+    Ok(violations)
+}
+
+/// The tables which the operands refer to.
+struct Tables {
+    atom_count: usize,
+    literal_count: usize,
+    imports: Vec<parts::Import>,
+    funs: Vec<parts::Function>,
+
+    /// The arities of the functions by the entry labels.
+    entries: BTreeMap<u32, u32>,
+}
+
+/// Checks the indices and the labels in the operands.
+///
+/// Returns `false` if there is an unknown label in `function`.
+fn check_operands(
+    function: &Function,
+    tables: &Tables,
+    found: &mut BTreeSet<(usize, &'static str)>,
+) -> bool {
+    use Operand::*;
+
+    let labels = function
+        .instructions
+        .iter()
+        .filter(|i| i.name() == "label")
+        .filter_map(|i| match i.args[..] {
+            [Unsigned(label)] => Some(label as u32),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+    let mut labels_ok = true;
+    for (instruction, &offset) in function.instructions.iter().zip(&function.offsets) {
+        let mut report = |reason| {
+            found.insert((offset, reason));
+        };
+        let name = instruction.name();
+        let args = &instruction.args[..];
+        let is_local_call = matches!(name, "call" | "call_last" | "call_only");
+        match (name, args) {
+            ("call" | "call_last" | "call_only", [Unsigned(arity), Label(label), ..]) => {
+                match tables.entries.get(label) {
+                    None => report("call to an unknown function"),
+                    Some(&a) if u64::from(a) != *arity => report("call arity mismatch"),
+                    Some(_) => {}
+                }
+            }
+            (
+                "call_ext" | "call_ext_last" | "call_ext_only",
+                [Unsigned(arity), Unsigned(i), ..],
+            ) => check_import(tables, *i, *arity, "call arity mismatch", &mut report),
+            ("bif0", [Unsigned(i), ..]) => {
+                check_import(tables, *i, 0, "bif arity mismatch", &mut report)
+            }
+            ("bif1", [_, Unsigned(i), ..]) => {
+                check_import(tables, *i, 1, "bif arity mismatch", &mut report)
+            }
+            ("bif2", [_, Unsigned(i), ..]) => {
+                check_import(tables, *i, 2, "bif arity mismatch", &mut report)
+            }
+            ("gc_bif1" | "gc_bif2" | "gc_bif3", [_, _, Unsigned(i), ..]) => {
+                let arity = u64::from(name.as_bytes()[6] - b'0');
+                check_import(tables, *i, arity, "bif arity mismatch", &mut report)
+            }
+            ("make_fun2" | "make_fun3", [Unsigned(i), ..]) if *i as usize >= tables.funs.len() => {
+                report("fun index out of range")
+            }
+            _ => {}
+        }
+
+        let mut operands = args.iter().collect::<Vec<_>>();
+        while let Some(operand) = operands.pop() {
+            match *operand {
+                Atom(i) if i as usize > tables.atom_count => report("atom index out of range"),
+                Literal(i) if i as usize >= tables.literal_count => {
+                    report("literal index out of range")
+                }
+                Label(0) => {}
+                Label(label) if !is_local_call && !labels.contains(&label) => {
+                    report("unknown label");
+                    labels_ok = false;
+                }
+                List(ref elements) => operands.extend(elements),
+                TypedRegister(ref register, _) => operands.push(register),
+                _ => {}
+            }
+        }
+    }
+    labels_ok
+}
+
+fn check_import(
+    tables: &Tables,
+    index: u64,
+    arity: u64,
+    mismatch: &'static str,
+    report: &mut impl FnMut(&'static str),
+) {
+    match tables.imports.get(index as usize) {
+        None => report("import index out of range"),
+        Some(import) if u64::from(import.arity) != arity => report(mismatch),
+        Some(_) => {}
+    }
+}
+
+/// The registers and the stack frame at a point in a function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    /// The initialized x registers.
+    x: BTreeSet<u32>,
+
+    /// The initialized y registers.
+    y: BTreeSet<u32>,
+
+    frame: Option<u32>,
+
+    /// The number of words reserved on the heap and not used yet.
+    heap: u32,
+}
+impl State {
+    /// Merges the state of another path, and returns `true` if `self` has changed.
+    fn merge(&mut self, other: &State) -> bool {
+        let x = self.x.intersection(&other.x).copied().collect();
+        let y = self.y.intersection(&other.y).copied().collect();
+        let heap = self.heap.min(other.heap);
+        let changed = x != self.x || y != self.y || heap != self.heap;
+        self.x = x;
+        self.y = y;
+        self.heap = heap;
+        changed
+    }
+}
+
+/// Checks the registers, the stack frame and the heap along the control-flow graph.
+fn check_flow(
+    function: &Function,
+    cfg: &ControlFlowGraph,
+    funs: &[parts::Function],
+    found: &mut BTreeSet<(usize, &'static str)>,
+) {
+    let effects = function
+        .instructions
+        .iter()
+        .map(|i| Effect::of(i, funs))
+        .collect::<Vec<_>>();
+    let mut states = vec![None::<State>; cfg.blocks.len()];
+    states[cfg.entry] = Some(State {
+        x: (0..function.arity).collect(),
+        y: BTreeSet::new(),
+        frame: None,
+        heap: 0,
+    });
+
+    // Computes the states at the starts of the blocks
+    let mut queue = vec![cfg.entry];
+    while let Some(b) = queue.pop() {
+        let block = &cfg.blocks[b];
+        let mut state = states[b].clone().expect("unreached block");
+        let mut fail = state.clone();
+        for i in block.range.clone() {
+            fail = step(
+                &mut state,
+                &function.instructions[i],
+                &effects[i],
+                &mut Vec::new(),
+            );
+        }
+        for edge in &block.edges {
+            let Some(target) = edge.target else {
+                continue;
+            };
+            let incoming = if edge.kind == EdgeKind::Fail {
+                &fail
+            } else {
+                &state
+            };
+            match states[target] {
+                Some(ref mut s) => {
+                    if s.frame != incoming.frame {
+                        let start = cfg.blocks[target].range.start;
+                        found.insert((function.offsets[start], "inconsistent stack frame"));
+                    }
+                    if s.merge(incoming) {
+                        queue.push(target);
+                    }
+                }
+                None => {
+                    states[target] = Some(incoming.clone());
+                    queue.push(target);
+                }
+            }
+        }
+    }
+
+    for (block, state) in cfg.blocks.iter().zip(states) {
+        let Some(mut state) = state else {
+            continue;
+        };
+        for i in block.range.clone() {
+            let mut reasons = Vec::new();
+            step(
+                &mut state,
+                &function.instructions[i],
+                &effects[i],
+                &mut reasons,
+            );
+            found.extend(reasons.into_iter().map(|r| (function.offsets[i], r)));
+        }
+    }
+}
+
+/// Executes `instruction` on `state`, and returns the state when the instruction fails (i.e.,
+/// without the registers it writes).
+fn step(
+    state: &mut State,
+    instruction: &Instruction,
+    effect: &Effect,
+    reasons: &mut Vec<&'static str>,
+) -> State {
+    use Operand::*;
+
+    let name = instruction.name();
+    let args = &instruction.args[..];
+    for x in &effect.uses.x {
+        if !state.x.contains(x) {
+            reasons.push("uninitialized x register");
+        }
+    }
+    for y in &effect.uses.y {
+        if check_y(state, *y, reasons) && !state.y.contains(y) {
+            reasons.push("uninitialized y register");
+        }
+    }
+
+    let dealloc = match (name, args) {
+        ("allocate" | "allocate_zero" | "allocate_heap" | "allocate_heap_zero", _) => {
+            if state.frame.is_some() {
+                reasons.push("stack frame already allocated");
+            }
+            None
+        }
+        ("deallocate", [Unsigned(n)])
+        | ("call_last" | "call_ext_last", [_, _, Unsigned(n)])
+        | ("apply_last", [_, Unsigned(n)]) => Some(*n as u32),
+        ("return" | "call_only" | "call_ext_only", _) => {
+            if state.frame.is_some() {
+                reasons.push("stack frame not deallocated");
+            }
+            None
+        }
+        ("trim", [Unsigned(n), _]) => {
+            if state.frame.is_none_or(|frame| frame < *n as u32) {
+                reasons.push("trim beyond the stack frame");
+            }
+            None
+        }
+        _ => None,
+    };
+    if let Some(n) = dealloc {
+        if state.frame != Some(n) {
+            reasons.push("deallocation does not match the stack frame");
+        }
+    }
+
+    // The y registers are scanned by the garbage collector
+    let is_gc = effect.x_limit.is_some()
+        && !name.starts_with("allocate")
+        && dealloc.is_none()
+        && !matches!(name, "call_only" | "call_ext_only");
+    if let Some(frame) = state.frame.filter(|_| is_gc) {
+        if (0..frame).any(|y| !state.y.contains(&y)) {
+            reasons.push("uninitialized y register in the stack frame");
+        }
+    }
+
+    let need = match (name, args) {
+        ("put_list", _) => 2,
+        ("put_tuple2", [_, List(elements)]) => elements.len() as u32 + 1,
+        ("update_record", [_, Unsigned(size), ..]) => *size as u32 + 1,
+        _ => 0,
+    };
+    if need > state.heap {
+        reasons.push("heap not reserved");
+    }
+    state.heap = state.heap.saturating_sub(need);
+
+    if let Some(limit) = effect.x_limit {
+        state.x.retain(|&x| x < limit);
+        state.heap = 0;
+    }
+    match (name, args) {
+        (
+            "allocate" | "allocate_zero" | "allocate_heap" | "allocate_heap_zero",
+            [Unsigned(n), ..],
+        ) => {
+            state.frame = Some(*n as u32);
+            state.y = if name.ends_with("_zero") {
+                (0..*n as u32).collect()
+            } else {
+                BTreeSet::new()
+            };
+        }
+        _ if dealloc.is_some() => {
+            state.frame = None;
+            state.y.clear();
+        }
+        ("trim", _) => {
+            state.frame = state.frame.map(|frame| frame.saturating_sub(effect.trim));
+            state.y = state
+                .y
+                .iter()
+                .filter_map(|y| y.checked_sub(effect.trim))
+                .collect();
+        }
+        _ => {}
+    }
+    match (name, args) {
+        ("test_heap", [heap, _]) | ("allocate_heap" | "allocate_heap_zero", [_, heap, _]) => {
+            state.heap = heap_need(heap).words;
+        }
+        _ => {}
+    }
+
+    let fail = state.clone();
+    state.x.extend(&effect.defs.x);
+    for y in &effect.defs.y {
+        check_y(state, *y, reasons);
+    }
+    state.y.extend(&effect.defs.y);
+    fail
+}
+
+/// Checks that `{y,Y}` is in the stack frame.
+fn check_y(state: &State, y: u32, reasons: &mut Vec<&'static str>) -> bool {
+    let reason = match state.frame {
+        None => "y register without a stack frame",
+        Some(frame) if y >= frame => "y register out of the stack frame",
+        Some(_) => return true,
+    };
+    reasons.push(reason);
+    false
+}
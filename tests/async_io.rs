@@ -0,0 +1,57 @@
+#![cfg(feature = "tokio")]
+use beam_file::chunk::{self, Chunk};
+use beam_file::{AsyncChunkReader, Error, RawBeamFile, StandardBeamFile};
+use std::path::PathBuf;
+
+#[tokio::test]
+async fn async_read_write() {
+    let original = std::fs::read(test_file("test.beam")).unwrap();
+    let beam = StandardBeamFile::from_async_reader(&original[..])
+        .await
+        .unwrap();
+    let expected = StandardBeamFile::from_reader(&original[..]).unwrap();
+    assert_eq!(expected.chunks, beam.chunks);
+
+    let mut encoded = Vec::new();
+    beam.to_async_writer(&mut encoded).await.unwrap();
+    let mut expected_encoded = Vec::new();
+    expected.to_writer(&mut expected_encoded).unwrap();
+    assert_eq!(expected_encoded, encoded);
+
+    let e = RawBeamFile::from_async_reader(&original[..original.len() - 1])
+        .await
+        .unwrap_err();
+    assert!(matches!(e, Error::TruncatedPayload { .. }));
+}
+
+#[tokio::test]
+async fn async_chunk_reader() {
+    let original = std::fs::read(test_file("Elixir.Unicode.beam")).unwrap();
+    let expected = RawBeamFile::from_reader(&original[..]).unwrap();
+
+    let mut reader = AsyncChunkReader::new(&original[..]).await.unwrap();
+    let mut chunks = Vec::new();
+    while let Some(chunk) = reader.next_chunk::<chunk::RawChunk>().await.unwrap() {
+        chunks.push(chunk);
+    }
+    assert_eq!(expected.chunks, chunks);
+
+    let mut reader = AsyncChunkReader::new(&original[..]).await.unwrap();
+    let atoms: chunk::StandardChunk = reader.next_chunk().await.unwrap().unwrap();
+    assert_eq!(b"AtU8", atoms.id());
+
+    let mut reader = AsyncChunkReader::new(&original[..original.len() - 1])
+        .await
+        .unwrap();
+    let mut result = Ok(None);
+    for _ in 0..expected.chunks.len() {
+        result = reader.next_chunk::<chunk::RawChunk>().await;
+    }
+    assert!(matches!(result, Err(Error::InChunk { .. })));
+}
+
+fn test_file(name: &str) -> PathBuf {
+    let mut path = PathBuf::from("tests/testdata/");
+    path.push(name);
+    path
+}
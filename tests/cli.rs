@@ -0,0 +1,154 @@
+#![cfg(feature = "cli")]
+use beam_file::chunk::{self, StandardChunk};
+use beam_file::{Assembly, RawBeamFile, StandardBeamFile};
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+#[test]
+fn info() {
+    let output = beam_file(&["info", test_file("test.beam").to_str().unwrap()]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("module:       test\nfile size:    1044\n"));
+    assert!(stdout.contains("\nAtom        20        79        1\n"));
+}
+
+#[test]
+fn chunks() {
+    let file = test_file("test.beam");
+    let output = beam_file(&["chunks", file.to_str().unwrap(), "Atom", "--hex"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(
+        "== Atom\n00000000: 0000 0009 0474 6573 7405 6865 6c6c 6f02  .....test.hello.\n"
+    ));
+
+    let output = beam_file(&["chunks", file.to_str().unwrap(), "ExpT"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("== ExpT\nExpT(\n    ExpTChunk {\n"));
+
+    let output = beam_file(&["chunks", file.to_str().unwrap(), "Xxxx"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "error: no such chunk: \"Xxxx\"\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+}
+
+#[test]
+fn extract_and_replace() {
+    let file = test_file("test.beam");
+    let dir = temp_dir("extract_and_replace");
+    let data = dir.join("StrT.bin");
+    let output = dir.join("test.beam");
+    std::fs::write(&data, b"foo").unwrap();
+
+    let status = beam_file(&[
+        "replace",
+        file.to_str().unwrap(),
+        "StrT",
+        data.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+    ])
+    .status;
+    assert!(status.success());
+    let beam = StandardBeamFile::from_file(&output).unwrap();
+    assert_eq!(
+        b"foo",
+        &beam.chunk::<chunk::StrTChunk>().unwrap().strings[..]
+    );
+
+    let status = beam_file(&[
+        "extract",
+        output.to_str().unwrap(),
+        "Atom",
+        data.to_str().unwrap(),
+    ])
+    .status;
+    assert!(status.success());
+    let original = RawBeamFile::from_file(&file).unwrap();
+    assert_eq!(
+        original.chunk_by_id(b"Atom").unwrap().data,
+        std::fs::read(&data).unwrap()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn json() {
+    let file = test_file("Elixir.Unicode.beam");
+    let output = beam_file(&["json", file.to_str().unwrap()]);
+    assert!(output.status.success());
+    let decoded: StandardBeamFile = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        StandardBeamFile::from_file(&file).unwrap().chunks,
+        decoded.chunks
+    );
+    assert!(matches!(decoded.chunks[0], StandardChunk::Atom(_)));
+}
+
+#[test]
+fn verify() {
+    let output = beam_file(&["verify", test_file("test.beam").to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let text = "
+{module, foo}.
+{exports, [{f,0}]}.
+{attributes, []}.
+{labels, 3}.
+
+{function, f, 0, 2}.
+  {label,1}.
+    {func_info,{atom,foo},{atom,f},0}.
+  {label,2}.
+    {move,{x,1},{x,0}}.
+    return.
+";
+    let dir = temp_dir("verify");
+    let file = dir.join("foo.beam");
+    let beam = text.parse::<Assembly>().unwrap().assemble().unwrap();
+    beam.to_file(&file).unwrap();
+    let output = beam_file(&["verify", file.to_str().unwrap()]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "f/0: offset - 8, reason - uninitialized x register\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+    assert_eq!(
+        "error: 1 violation(s) found\n",
+        String::from_utf8(output.stderr).unwrap()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn usage() {
+    let output = beam_file(&["unknown"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Usage: beam-file"));
+}
+
+fn beam_file(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_beam-file"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Creates a directory for the files written by the test `name`.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("beam_file_cli_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn test_file(name: &str) -> PathBuf {
+    let mut path = PathBuf::from("tests/testdata/");
+    path.push(name);
+    path
+}
@@ -2,6 +2,12 @@ use beam_file::chunk;
 use beam_file::chunk::Chunk;
 use beam_file::parts;
 use beam_file::BeamFile;
+use beam_file::BeamInfo;
+use beam_file::DecodeLimits;
+use beam_file::Error;
+use beam_file::LenientBeamFile;
+use beam_file::Limit;
+use beam_file::LosslessBeamFile;
 use beam_file::RawBeamFile;
 use beam_file::Result;
 use beam_file::StandardBeamFile;
@@ -226,6 +232,1397 @@ fn encode_chunks() {
     assert_eq!(original, encoded);
 }
 
+#[test]
+fn typed_chunk_accessors() {
+    let mut beam = StandardBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+
+    // `AtU8` is accessible as `AtomChunk`
+    let atoms = beam.chunk::<chunk::AtomChunk>().unwrap();
+    assert!(atoms.is_unicode);
+    assert_eq!("Elixir.Unicode", atoms.atoms[0].name);
+    assert!(beam.chunk::<chunk::AbstChunk>().is_none());
+
+    // Mutation
+    beam.chunk_mut::<chunk::ExpTChunk>()
+        .unwrap()
+        .exports
+        .clear();
+    assert!(beam.chunk::<chunk::ExpTChunk>().unwrap().exports.is_empty());
+
+    // An `Atom` chunk replaces the `AtU8` chunk in place
+    let old = beam.insert_or_replace(chunk::AtomChunk {
+        is_unicode: false,
+        atoms: Vec::new(),
+    });
+    assert_eq!(b"AtU8", old.unwrap().id());
+    assert_eq!(b"Atom", beam.chunks[0].id());
+
+    // Appended if missing
+    assert!(beam
+        .insert_or_replace(chunk::AbstChunk { term: Vec::new() })
+        .is_none());
+    assert_eq!(b"Abst", beam.chunks.last().unwrap().id());
+
+    // Access by identifier
+    assert!(beam.chunk_by_id(b"Line").is_some());
+    assert_eq!(b"Line", beam.remove(b"Line").unwrap().id());
+    assert!(beam.chunk_by_id(b"Line").is_none());
+    assert!(beam.remove(b"Line").is_none());
+}
+
+#[derive(Debug, PartialEq)]
+struct SignChunk {
+    signature: Vec<u8>,
+}
+impl chunk::Chunk for SignChunk {
+    fn id(&self) -> &chunk::Id {
+        b"Sign"
+    }
+    fn decode_data<R: Read>(_id: &chunk::Id, mut reader: R) -> Result<Self> {
+        let mut signature = Vec::new();
+        reader.read_to_end(&mut signature)?;
+        Ok(SignChunk { signature })
+    }
+    fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(&self.signature)?;
+        Ok(())
+    }
+}
+impl chunk::TypedChunk for SignChunk {
+    const IDS: &'static [chunk::Id] = &[*b"Sign"];
+}
+
+#[derive(Debug, PartialEq)]
+struct ProvChunk {
+    builder: String,
+}
+impl chunk::Chunk for ProvChunk {
+    fn id(&self) -> &chunk::Id {
+        b"Prov"
+    }
+    fn decode_data<R: Read>(_id: &chunk::Id, mut reader: R) -> Result<Self> {
+        let mut builder = String::new();
+        reader.read_to_string(&mut builder)?;
+        Ok(ProvChunk { builder })
+    }
+    fn encode_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(self.builder.as_bytes())?;
+        Ok(())
+    }
+}
+impl chunk::TypedChunk for ProvChunk {
+    const IDS: &'static [chunk::Id] = &[*b"Prov"];
+}
+
+#[test]
+fn extended_chunks() {
+    type MyChunk =
+        chunk::ExtendedChunk<chunk::ExtendedChunk<chunk::StandardChunk, SignChunk>, ProvChunk>;
+
+    let mut beam = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    beam.chunks.push(chunk::RawChunk {
+        id: *b"Sign",
+        data: vec![1, 2, 3],
+    });
+    beam.chunks.push(chunk::RawChunk {
+        id: *b"Prov",
+        data: b"ci".to_vec(),
+    });
+    let mut buf = Vec::new();
+    beam.to_writer(&mut buf).unwrap();
+
+    let beam = BeamFile::<MyChunk>::from_reader(&buf[..]).unwrap();
+    assert_eq!(
+        "test",
+        beam.chunk::<chunk::AtomChunk>().unwrap().atoms[0].name
+    );
+    assert_eq!(
+        Some(&SignChunk {
+            signature: vec![1, 2, 3]
+        }),
+        beam.chunk_by_id(b"Sign")
+            .and_then(|c| c.as_base())
+            .and_then(|c| c.as_user())
+    );
+    assert_eq!(
+        Some(&ProvChunk {
+            builder: "ci".to_string()
+        }),
+        beam.chunk_by_id(b"Prov").and_then(|c| c.as_user())
+    );
+
+    let mut encoded = Vec::new();
+    beam.to_writer(&mut encoded).unwrap();
+    let beam = RawBeamFile::from_reader(&encoded[..]).unwrap();
+    assert_eq!(vec![1, 2, 3], beam.chunk_by_id(b"Sign").unwrap().data);
+    assert_eq!(b"ci", &beam.chunk_by_id(b"Prov").unwrap().data[..]);
+
+    // Typed access to the user-defined chunks
+    let mut beam = BeamFile::<MyChunk>::from_reader(&buf[..]).unwrap();
+    assert_eq!("ci", beam.user_chunk().unwrap().builder);
+    beam.user_chunk_mut().unwrap().builder = "local".to_string();
+    let old = beam.insert_or_replace_user(ProvChunk {
+        builder: "release".to_string(),
+    });
+    assert_eq!("local", old.unwrap().as_user().unwrap().builder);
+    assert_eq!("release", beam.user_chunk().unwrap().builder);
+
+    let sign = beam
+        .chunks
+        .iter_mut()
+        .find_map(|c| c.as_base_mut().and_then(|c| c.as_user_mut()));
+    sign.unwrap().signature.clear();
+    let sign = beam.chunk_by_id(b"Sign").and_then(|c| c.as_base());
+    assert!(sign.unwrap().as_user().unwrap().signature.is_empty());
+
+    let mut beam = BeamFile::<MyChunk>::from_file(test_file("test.beam")).unwrap();
+    assert!(beam.user_chunk().is_none());
+    let old = beam.insert_or_replace_user(ProvChunk {
+        builder: "ci".to_string(),
+    });
+    assert!(old.is_none());
+    assert_eq!(b"Prov", beam.chunks.last().unwrap().id());
+}
+
+#[test]
+fn lenient_chunks() {
+    let mut raw = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    raw.chunk_by_id_mut(b"LitT").unwrap().data = vec![0, 0, 0, 1, 0xFF];
+    let mut buf = Vec::new();
+    raw.to_writer(&mut buf).unwrap();
+
+    assert!(StandardBeamFile::from_reader(&buf[..]).is_err());
+    assert!(raw.decode::<chunk::StandardChunk>().is_err());
+
+    let beam = LenientBeamFile::from_reader(&buf[..]).unwrap();
+    assert_eq!(collect_id(&raw.chunks), collect_id(&beam.chunks));
+    let malformed = beam
+        .chunks
+        .iter()
+        .filter(|c| c.error().is_some())
+        .map(|c| c.id())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![b"LitT"], malformed);
+    assert!(beam.chunk::<chunk::LitTChunk>().is_none());
+    assert_eq!(
+        "test",
+        beam.chunk::<chunk::AtomChunk>().unwrap().atoms[0].name
+    );
+
+    // Malformed chunks are written back as they were
+    let mut encoded = Vec::new();
+    beam.to_writer(&mut encoded).unwrap();
+    let reread = RawBeamFile::from_reader(&encoded[..]).unwrap();
+    assert_eq!(raw.chunk_by_id(b"LitT"), reread.chunk_by_id(b"LitT"));
+
+    // Chunk by chunk conversion
+    let beam = raw.decode_lenient::<chunk::StandardChunk>();
+    assert_eq!(
+        1,
+        beam.chunks.iter().filter(|c| c.error().is_some()).count()
+    );
+}
+
+#[test]
+fn decode_limits() {
+    let original = std::fs::read(test_file("test.beam")).unwrap();
+    let decode = |limits: &DecodeLimits| {
+        StandardBeamFile::from_reader_with_limits(&original[..], limits).map(|_| ())
+    };
+    let limit_of = |result: Result<()>| match result.as_ref().map_err(|e| e.root_cause()) {
+        Err(Error::LimitExceeded { limit, .. }) => Some(*limit),
+        _ => None,
+    };
+
+    assert!(decode(&DecodeLimits::default()).is_ok());
+    let limits = DecodeLimits {
+        max_file_size: original.len() as u64 - 1,
+        ..DecodeLimits::default()
+    };
+    assert_eq!(Some(Limit::FileSize), limit_of(decode(&limits)));
+    let limits = DecodeLimits {
+        max_chunk_size: 128,
+        ..DecodeLimits::default()
+    };
+    assert_eq!(Some(Limit::ChunkSize), limit_of(decode(&limits)));
+    let limits = DecodeLimits {
+        max_entries: 8,
+        ..DecodeLimits::default()
+    };
+    assert_eq!(Some(Limit::Entries), limit_of(decode(&limits)));
+    let limits = DecodeLimits {
+        max_literal_size: 16,
+        ..DecodeLimits::default()
+    };
+    assert_eq!(Some(Limit::LiteralSize), limit_of(decode(&limits)));
+
+    // Huge counts and sizes are not trusted for allocation
+    let mut raw = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    raw.chunk_by_id_mut(b"ImpT").unwrap().data = vec![0xFF; 4];
+    let mut buf = Vec::new();
+    raw.to_writer(&mut buf).unwrap();
+    assert!(StandardBeamFile::from_reader(&buf[..]).is_err());
+
+    let mut buf = original.clone();
+    buf[16..20].copy_from_slice(&[0xFF; 4]); // The data size of the first chunk
+    assert!(RawBeamFile::from_reader(&buf[..]).is_err());
+
+    // The line and file counts of "Line" are entries
+    let mut data = vec![0; 12];
+    data.extend_from_slice(&[0xFF; 4]); // The line count
+    data.extend_from_slice(&[0; 4]); // The file count
+    let limits = DecodeLimits {
+        max_entries: 8,
+        ..DecodeLimits::default()
+    };
+    let result = chunk::LineChunk::decode_data_with_limits(b"Line", &data[..], &limits);
+    assert_eq!(Some(Limit::Entries), limit_of(result.map(|_| ())));
+    data[12..16].copy_from_slice(&[0; 4]);
+    data[16..20].copy_from_slice(&[0xFF; 4]);
+    let result = chunk::LineChunk::decode_data_with_limits(b"Line", &data[..], &limits);
+    assert_eq!(Some(Limit::Entries), limit_of(result.map(|_| ())));
+}
+
+#[test]
+fn error_context() {
+    let original = std::fs::read(test_file("test.beam")).unwrap();
+
+    // The second import has a bad arity field
+    let mut raw = RawBeamFile::from_reader(&original[..]).unwrap();
+    raw.chunk_by_id_mut(b"ImpT")
+        .unwrap()
+        .data
+        .truncate(4 + 12 + 8);
+    let mut buf = Vec::new();
+    raw.to_writer(&mut buf).unwrap();
+    let e = StandardBeamFile::from_reader(&buf[..]).unwrap_err();
+    match e {
+        Error::InChunk {
+            id,
+            offset,
+            ref source,
+        } => {
+            assert_eq!(b"ImpT", &id);
+            assert_eq!(&buf[offset as usize..][..4], b"ImpT");
+            match **source {
+                Error::InEntry {
+                    index: 1,
+                    offset: entry_offset,
+                    ..
+                } => {
+                    assert_eq!(offset + 8 + 16, entry_offset);
+                    let data = &raw.chunk_by_id(b"ImpT").unwrap().data;
+                    assert_eq!(&buf[entry_offset as usize..][..8], &data[16..]);
+                }
+                ref e => panic!("unexpected error: {e:?}"),
+            }
+        }
+        _ => panic!("{:?}", e),
+    }
+    assert!(matches!(e.root_cause(), Error::Io(_)));
+    assert!(e.to_string().starts_with("\"ImpT\" at offset "));
+
+    // Truncated payload
+    let e = RawBeamFile::from_reader(&original[..original.len() - 1]).unwrap_err();
+    assert!(matches!(e, Error::TruncatedPayload { .. }));
+
+    // Trailing garbage
+    let mut buf = original.clone();
+    buf.extend_from_slice(&[0; 4]);
+    let payload_size = (buf.len() - 8) as u32;
+    buf[4..8].copy_from_slice(&payload_size.to_be_bytes());
+    let e = RawBeamFile::from_reader(&buf[..]).unwrap_err();
+    assert!(matches!(
+        e,
+        Error::TrailingGarbage { offset, size: 4 } if offset == original.len() as u64
+    ));
+
+    // Truncated file and file path
+    let path = std::env::temp_dir().join(format!(
+        "beam_file_error_context_{}.beam",
+        std::process::id()
+    ));
+    std::fs::write(&path, &buf[..buf.len() - 4]).unwrap();
+    let e = RawBeamFile::from_file(&path).unwrap_err();
+    match e {
+        Error::InFile {
+            path: ref p,
+            ref source,
+        } => {
+            assert_eq!(&path, p);
+            assert!(matches!(**source, Error::TruncatedPayload { .. }));
+        }
+        _ => panic!("{:?}", e),
+    }
+    let e2 = RawBeamFile::from_reader(&buf[..buf.len() - 4]).unwrap_err();
+    assert_eq!(e.root_cause().to_string(), e2.to_string());
+
+    // Trailing bytes are reported in the same way by `from_reader` and `from_file`
+    let mut trailing = original.clone();
+    trailing.extend_from_slice(&[0; 4]);
+    std::fs::write(&path, &trailing).unwrap();
+    let e = RawBeamFile::from_file(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(
+        e.root_cause(),
+        Error::TrailingGarbage { offset, size: 4 } if *offset == original.len() as u64
+    ));
+}
+
+#[test]
+fn encode_errors() {
+    let mut beam = StandardBeamFile::from_file(test_file("test.beam")).unwrap();
+    beam.chunk_mut::<chunk::AtomChunk>()
+        .unwrap()
+        .atoms
+        .push(parts::Atom {
+            name: "a".repeat(256),
+        });
+    let e = beam.to_writer(Vec::new()).unwrap_err();
+    assert!(matches!(e, Error::AtomTooLong { index: 9, len: 256 }));
+}
+
+#[test]
+fn lossless_round_trip() {
+    for name in ["test.beam", "Elixir.Unicode.beam"] {
+        let original = std::fs::read(test_file(name)).unwrap();
+        let beam = LosslessBeamFile::<chunk::StandardChunk>::from_reader(&original[..]).unwrap();
+        let mut encoded = Vec::new();
+        beam.to_writer(&mut encoded).unwrap();
+        assert_eq!(original, encoded, "{}", name);
+
+        // Only the modified chunk is re-encoded
+        let mut beam = beam;
+        beam.beam.chunk_mut::<chunk::StrTChunk>().unwrap().strings = b"foo".to_vec();
+        let mut encoded = Vec::new();
+        beam.to_writer(&mut encoded).unwrap();
+        let before = RawBeamFile::from_reader(&original[..]).unwrap();
+        let after = RawBeamFile::from_reader(&encoded[..]).unwrap();
+        assert_eq!(collect_id(&before.chunks), collect_id(&after.chunks));
+        for (b, a) in before.chunks.iter().zip(after.chunks.iter()) {
+            if a.id() == b"StrT" {
+                assert_eq!(b"foo", &a.data[..]);
+            } else {
+                assert_eq!(b, a);
+            }
+        }
+    }
+
+    // Non-zero padding and trailing bytes are kept
+    let mut raw = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    raw.chunk_by_id_mut(b"StrT").unwrap().data = vec![1];
+    let mut original = Vec::new();
+    raw.to_writer(&mut original).unwrap();
+    let strt = original.windows(4).position(|w| w == b"StrT").unwrap();
+    original[strt + 9..strt + 12].copy_from_slice(&[7, 7, 7]);
+    original.extend_from_slice(b"xyz");
+    let payload_size = (original.len() - 8) as u32;
+    original[4..8].copy_from_slice(&payload_size.to_be_bytes());
+    original.extend_from_slice(b"trailing");
+
+    let beam = LosslessBeamFile::<chunk::StandardChunk>::from_reader(&original[..]).unwrap();
+    assert_eq!(b"xyz", &beam.trailing_payload[..]);
+    assert_eq!(b"trailing", &beam.trailing[..]);
+    let mut encoded = Vec::new();
+    beam.to_writer(&mut encoded).unwrap();
+    assert_eq!(original, encoded);
+
+    // The trailing bytes count toward the file size limit
+    let mut limits = DecodeLimits {
+        max_file_size: original.len() as u64,
+        ..DecodeLimits::default()
+    };
+    assert!(
+        LosslessBeamFile::<chunk::StandardChunk>::from_reader_with_limits(&original[..], &limits)
+            .is_ok()
+    );
+    limits.max_file_size -= 1;
+    let e =
+        LosslessBeamFile::<chunk::StandardChunk>::from_reader_with_limits(&original[..], &limits)
+            .unwrap_err();
+    assert!(matches!(
+        e,
+        Error::LimitExceeded {
+            limit: Limit::FileSize,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn beam_info() {
+    let bytes = std::fs::read(test_file("Elixir.Unicode.beam")).unwrap();
+    let info = BeamInfo::from_slice(&bytes).unwrap();
+    assert_eq!(
+        info,
+        BeamInfo::from_file(test_file("Elixir.Unicode.beam")).unwrap()
+    );
+    assert_eq!(Some("Elixir.Unicode"), info.module.as_deref());
+    assert_eq!(bytes.len() as u64, info.file_size());
+
+    let raw = RawBeamFile::from_reader(&bytes[..]).unwrap();
+    assert_eq!(collect_id(&raw.chunks), collect_id_of_info(&info));
+    let mut offset = 12;
+    for (c, i) in raw.chunks.iter().zip(info.chunks.iter()) {
+        assert_eq!(offset, i.header_offset());
+        assert_eq!(&bytes[i.offset as usize..][..i.size as usize], &c.data[..]);
+        assert_eq!(0, (i.size + i.padding) % 4);
+        offset += 8 + u64::from(i.size + i.padding);
+    }
+    assert_eq!(bytes.len() as u64, offset);
+
+    // Payload size validation
+    let mut longer = bytes.clone();
+    longer.push(0);
+    let e = BeamInfo::from_slice(&longer).unwrap_err();
+    assert!(matches!(
+        e,
+        Error::TrailingGarbage { offset, size: 1 } if offset == bytes.len() as u64
+    ));
+    let path =
+        std::env::temp_dir().join(format!("beam_file_beam_info_{}.beam", std::process::id()));
+    std::fs::write(&path, &longer).unwrap();
+    let file_error = RawBeamFile::from_file(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(e.to_string(), file_error.root_cause().to_string());
+    assert!(matches!(
+        BeamInfo::from_slice(&bytes[..bytes.len() - 1]),
+        Err(Error::InChunk { .. })
+    ));
+}
+
+fn collect_id_of_info(info: &BeamInfo) -> Vec<String> {
+    info.chunks
+        .iter()
+        .map(|c| std::str::from_utf8(&c.id).unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn seekable_writer() {
+    let beam = StandardBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    let mut expected = Vec::new();
+    beam.to_writer(&mut expected).unwrap();
+
+    // The writer does not have to be at the beginning
+    let mut cursor = std::io::Cursor::new(b"prefix".to_vec());
+    cursor.set_position(6);
+    beam.to_seekable_writer(&mut cursor).unwrap();
+    assert_eq!(b"prefix", &cursor.get_ref()[..6]);
+    assert_eq!(expected, &cursor.get_ref()[6..]);
+
+    let original = std::fs::read(test_file("test.beam")).unwrap();
+    let mut beam = LosslessBeamFile::<chunk::StandardChunk>::from_reader(&original[..]).unwrap();
+    beam.beam.chunk_mut::<chunk::LitTChunk>().unwrap();
+    beam.trailing = b"trailing".to_vec();
+    let mut expected = Vec::new();
+    beam.to_writer(&mut expected).unwrap();
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    beam.to_seekable_writer(&mut cursor).unwrap();
+    assert_eq!(expected, cursor.into_inner());
+}
+
+#[test]
+fn scanner() {
+    let dir = std::env::temp_dir().join("beam_file_scanner");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("a/ebin")).unwrap();
+    std::fs::create_dir_all(dir.join("b/ebin")).unwrap();
+    std::fs::copy(test_file("test.beam"), dir.join("a/ebin/test.beam")).unwrap();
+    std::fs::copy(
+        test_file("Elixir.Unicode.beam"),
+        dir.join("b/ebin/Elixir.Unicode.beam"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("b/ebin/broken.beam"), b"FOR1").unwrap();
+    std::fs::write(dir.join("b/ebin/README"), b"not a beam").unwrap();
+
+    let done = std::sync::Mutex::new(Vec::new());
+    let scanner = beam_file::Scanner {
+        chunk_ids: Some(vec![*b"ExpT"]),
+        threads: 2,
+        ..Default::default()
+    };
+    let entries = scanner.scan_with_progress::<chunk::StandardChunk, _, _>(&[&dir], |p| {
+        assert_eq!(3, p.total);
+        done.lock().unwrap().push(p.done);
+    });
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut done = done.into_inner().unwrap();
+    done.sort();
+    assert_eq!(vec![1, 2, 3], done);
+
+    let paths = entries
+        .iter()
+        .map(|e| e.path.strip_prefix(&dir).unwrap().to_path_buf())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            PathBuf::from("a/ebin/test.beam"),
+            PathBuf::from("b/ebin/Elixir.Unicode.beam"),
+            PathBuf::from("b/ebin/broken.beam"),
+        ],
+        paths
+    );
+    let beam = entries[0].result.as_ref().unwrap();
+    assert_eq!(vec!["ExpT"], collect_id(&beam.chunks));
+    assert!(entries[1].result.is_ok());
+    assert!(matches!(
+        entries[2].result,
+        Err(Error::InFile { ref path, .. }) if path == &entries[2].path
+    ));
+}
+
+#[test]
+fn code_path() {
+    let dir = std::env::temp_dir().join("beam_file_code_path");
+    let _ = std::fs::remove_dir_all(&dir);
+    for ebin in [
+        "lib/foo-1.2/ebin",
+        "lib/foo-1.10/ebin",
+        "lib/bar/ebin",
+        "patches",
+    ] {
+        std::fs::create_dir_all(dir.join(ebin)).unwrap();
+    }
+    let test_beam = test_file("test.beam");
+    std::fs::copy(&test_beam, dir.join("lib/foo-1.2/ebin/test.beam")).unwrap();
+    std::fs::copy(&test_beam, dir.join("lib/foo-1.10/ebin/test.beam")).unwrap();
+    std::fs::copy(&test_beam, dir.join("lib/bar/ebin/renamed.beam")).unwrap();
+    std::fs::copy(&test_beam, dir.join("patches/test.beam")).unwrap();
+    std::fs::write(dir.join("patches/broken.beam"), b"FOR1").unwrap();
+
+    let mut code_path = beam_file::CodePath::new();
+    code_path.add_dir(dir.join("patches"));
+    code_path.add_dir(dir.join("no_such_dir"));
+    code_path.add_lib_root(dir.join("lib")).unwrap();
+    assert_eq!(
+        &[
+            dir.join("patches"),
+            dir.join("no_such_dir"),
+            dir.join("lib/bar/ebin"),
+            dir.join("lib/foo-1.10/ebin"),
+        ],
+        code_path.dirs()
+    );
+
+    let index = code_path.index();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(1, index.errors.len());
+    assert_eq!(dir.join("patches/broken.beam"), index.errors[0].0);
+    assert_eq!(3, index.files().len());
+
+    let file = index.resolve("test").unwrap();
+    assert_eq!(dir.join("patches/test.beam"), file.path);
+    assert!(index.resolve("renamed").is_some());
+    assert!(index.resolve("no_such_module").is_none());
+
+    assert_eq!(
+        vec![
+            beam_file::Conflict::NameMismatch {
+                path: dir.join("lib/bar/ebin/renamed.beam"),
+                module: "test".to_string(),
+            },
+            beam_file::Conflict::Duplicate {
+                module: "test".to_string(),
+                paths: vec![
+                    dir.join("patches/test.beam"),
+                    dir.join("lib/bar/ebin/renamed.beam"),
+                    dir.join("lib/foo-1.10/ebin/test.beam"),
+                ],
+            },
+        ],
+        index.conflicts()
+    );
+}
+
+#[test]
+fn beam_index() {
+    let dir = std::env::temp_dir().join("beam_file_beam_index");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(test_file("test.beam"), dir.join("a.beam")).unwrap();
+    std::fs::copy(test_file("test.beam"), dir.join("b.beam")).unwrap();
+    std::fs::copy(test_file("test.beam"), dir.join("c.beam")).unwrap();
+    let index_file = dir.join("index.bix");
+
+    let mut index = beam_file::BeamIndex::new();
+    let update = index.update(&[&dir]);
+    assert_eq!(3, update.parsed.len());
+    assert!(update.errors.is_empty());
+    index.to_file(&index_file).unwrap();
+
+    let entry = index.get(dir.join("a.beam")).unwrap();
+    assert_eq!("test", entry.module);
+    assert_eq!(1044, entry.size);
+    assert!(entry
+        .exports
+        .iter()
+        .any(|f| f.module == "test" && f.function == "hello" && f.arity == 1));
+    assert!(entry
+        .imports
+        .iter()
+        .any(|f| f.module == "erlang" && f.function == "get_module_info"));
+    assert!(!entry.attributes.is_empty());
+
+    // `a.beam` is replaced, `b.beam` is only touched and `c.beam` is removed
+    std::fs::copy(test_file("Elixir.Unicode.beam"), dir.join("a.beam")).unwrap();
+    let b = File::options()
+        .write(true)
+        .open(dir.join("b.beam"))
+        .unwrap();
+    b.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+    std::fs::remove_file(dir.join("c.beam")).unwrap();
+
+    let mut index = beam_file::BeamIndex::from_file(&index_file).unwrap();
+    let update = index.update(&[&dir]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(vec![dir.join("a.beam"), dir.join("b.beam")], update.parsed);
+    assert_eq!(vec![dir.join("c.beam")], update.removed);
+    assert_eq!(
+        "Elixir.Unicode",
+        index.get(dir.join("a.beam")).unwrap().module
+    );
+    let b = index.get(dir.join("b.beam")).unwrap();
+    assert_eq!(std::time::SystemTime::UNIX_EPOCH, b.mtime);
+    assert_eq!("test", b.module);
+    assert_eq!(2, index.entries().count());
+
+    let e = beam_file::BeamIndex::from_reader(&b"FOR1"[..]).unwrap_err();
+    assert!(matches!(e, Error::UnexpectedMagicNumber { .. }));
+
+    // The entry of a file whose metadata cannot be read is kept
+    let missing = dir.join("a.beam");
+    let update = index.update(&[&missing]);
+    assert_eq!(1, update.errors.len());
+    assert!(!update.removed.contains(&missing));
+    assert!(index.get(&missing).is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn beam_index_non_utf8_path() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = std::env::temp_dir().join("beam_file_beam_index_non_utf8");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(std::ffi::OsStr::from_bytes(b"\xFF.beam"));
+    std::fs::copy(test_file("test.beam"), &path).unwrap();
+
+    let mut index = beam_file::BeamIndex::new();
+    let update = index.update(&[&dir]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(vec![path.clone()], update.parsed);
+
+    let mut buf = Vec::new();
+    index.to_writer(&mut buf).unwrap();
+    let index = beam_file::BeamIndex::from_reader(&buf[..]).unwrap();
+    assert_eq!(path, index.get(&path).unwrap().path);
+}
+
+#[test]
+fn escript() {
+    let test = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    let unicode = RawBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+
+    // Archive
+    let body =
+        beam_file::EscriptBody::archive([("test.beam", &test), ("Elixir.Unicode.beam", &unicode)])
+            .unwrap();
+    let mut escript = beam_file::Escript::new(body);
+    escript.emu_args = Some("-escript main test".to_string());
+    let path = std::env::temp_dir().join("beam_file_escript");
+    escript.to_file(&path).unwrap();
+    let read = beam_file::Escript::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(escript, read);
+    let modules = read.modules::<chunk::StandardChunk>().unwrap();
+    assert_eq!(2, modules.len());
+    assert_eq!("Elixir.Unicode.beam", modules[1].path);
+    assert_eq!(
+        "Elixir.Unicode",
+        modules[1].beam.chunk::<chunk::AtomChunk>().unwrap().atoms[0].name
+    );
+
+    // Bare BEAM file without a comment line
+    let mut escript = beam_file::Escript::new(beam_file::EscriptBody::beam(&test).unwrap());
+    escript.comment = None;
+    escript.emu_args = Some("-smp".to_string());
+    let mut bytes = Vec::new();
+    escript.to_writer(&mut bytes).unwrap();
+    assert!(bytes.starts_with(b"#!/usr/bin/env escript\n%%!-smp\nFOR1"));
+    let read = beam_file::Escript::from_slice(&bytes).unwrap();
+    assert_eq!(escript, read);
+    let modules = read.modules::<chunk::RawChunk>().unwrap();
+    assert_eq!("", modules[0].path);
+    assert_eq!(test.chunks, modules[0].beam.chunks);
+
+    // Source code
+    let bytes = b"#!/usr/bin/env escript\r\n%% -*- erlang -*-\nmain(_) -> ok.\n";
+    let read = beam_file::Escript::from_slice(bytes).unwrap();
+    assert_eq!(Some("/usr/bin/env escript"), read.shebang.as_deref());
+    assert_eq!(Some("-*- erlang -*-"), read.comment.as_deref());
+    assert_eq!(None, read.emu_args);
+    assert_eq!(
+        beam_file::EscriptBody::Source(b"main(_) -> ok.\n".to_vec()),
+        read.body
+    );
+    assert!(read.modules::<chunk::RawChunk>().unwrap().is_empty());
+
+    // A corrupted archive entry
+    let mut bytes = Vec::new();
+    beam_file::Escript::new(beam_file::EscriptBody::archive([("test.beam", &test)]).unwrap())
+        .to_writer(&mut bytes)
+        .unwrap();
+    let data = bytes.windows(9).position(|w| w == b"test.beam").unwrap() + 9;
+    bytes[data + 20] ^= 0xFF;
+    let e = beam_file::Escript::from_slice(&bytes)
+        .unwrap()
+        .modules::<chunk::RawChunk>()
+        .unwrap_err();
+    assert!(matches!(e, Error::InArchiveEntry { ref name, .. } if name == "test.beam"));
+
+    // Limits
+    let limits = DecodeLimits {
+        max_file_size: bytes.len() as u64 - 1,
+        ..DecodeLimits::default()
+    };
+    let e = beam_file::Escript::from_reader_with_limits(&bytes[..], &limits).unwrap_err();
+    assert!(matches!(e, Error::LimitExceeded { .. }));
+    let limits = DecodeLimits {
+        max_file_size: 1000,
+        ..DecodeLimits::default()
+    };
+    let e = beam_file::Escript::from_slice(&bytes)
+        .unwrap()
+        .modules_with_limits::<chunk::RawChunk>(&limits)
+        .unwrap_err();
+    assert!(matches!(e.root_cause(), Error::LimitExceeded { .. }));
+}
+
+#[test]
+fn ez_archive() {
+    let bytes = std::fs::read(test_file("foo-1.0.ez")).unwrap();
+    let archive = beam_file::archive::EzArchive::new(&bytes).unwrap();
+    assert_eq!(7, archive.zip().entries().len());
+    let names = archive
+        .beam_entries()
+        .map(|e| e.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["foo-1.0/ebin/test.beam", "foo-1.0/ebin/Elixir.Unicode.beam"],
+        names
+    );
+
+    // Both stored and deflated entries
+    let test = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    let unicode = RawBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    let modules = archive.modules::<chunk::RawChunk>().unwrap();
+    assert_eq!(2, modules.len());
+    assert_eq!(test.chunks, modules[0].beam.chunks);
+    assert_eq!(unicode.chunks, modules[1].beam.chunks);
+
+    let app = archive.app_file().unwrap().unwrap();
+    assert_eq!("foo-1.0/ebin/foo.app", app.path);
+    assert_eq!(
+        &b"{application,foo,[{vsn,\"1.0\"},{modules,[test,'Elixir.Unicode']}]}.\n"[..],
+        &app.data[..]
+    );
+
+    // A corrupted entry
+    let mut bytes = bytes;
+    let data = bytes
+        .windows(22)
+        .position(|w| w == b"foo-1.0/ebin/test.beam")
+        .unwrap()
+        + 22;
+    bytes[data + 20] ^= 0xFF;
+    let e = beam_file::archive::EzArchive::new(&bytes)
+        .unwrap()
+        .modules::<chunk::RawChunk>()
+        .unwrap_err();
+    assert!(
+        matches!(e, Error::InArchiveEntry { ref name, .. } if name == "foo-1.0/ebin/test.beam")
+    );
+}
+
+#[test]
+fn release_reader() {
+    use beam_file::archive::{ReleaseItem, ReleaseReader};
+
+    let bytes = std::fs::read(test_file("foo-1.0.tar.gz")).unwrap();
+    let items = ReleaseReader::<_, chunk::RawChunk>::new(&bytes[..])
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    let test = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    let unicode = RawBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    let long_path = format!(
+        "lib/{}-1.0/ebin/Elixir.Unicode.beam",
+        "elixir_unicode_".repeat(7)
+    );
+    let expected = [
+        ("lib/test-1.0/ebin/test.beam", Some(&test)),
+        (&long_path, Some(&unicode)),
+        ("lib/foo-1.0.ez/foo-1.0/ebin/test.beam", Some(&test)),
+        (
+            "lib/foo-1.0.ez/foo-1.0/ebin/Elixir.Unicode.beam",
+            Some(&unicode),
+        ),
+        ("releases/1.0/foo.rel", None),
+    ];
+    assert_eq!(expected.len(), items.len());
+    for ((path, beam), item) in expected.iter().zip(&items) {
+        match (item, beam) {
+            (ReleaseItem::Beam(item), Some(beam)) => {
+                assert_eq!(path, &item.path);
+                assert_eq!(beam.chunks, item.beam.chunks);
+            }
+            (ReleaseItem::Rel(item), None) => {
+                assert_eq!(path, &item.path);
+                assert!(item.data.starts_with(b"{release,{\"foo\",\"1.0\"}"));
+            }
+            _ => panic!("unexpected item: {:?}", item),
+        }
+    }
+
+    // A truncated tarball
+    let mut reader = ReleaseReader::<_, chunk::RawChunk>::new(&bytes[..bytes.len() / 2]).unwrap();
+    assert!(reader.by_ref().any(|item| item.is_err()));
+    assert!(reader.next().is_none());
+
+    // Members larger than the limit (including the `.ez` archive) are reported one by one
+    let limits = DecodeLimits {
+        max_file_size: 1044,
+        ..DecodeLimits::default()
+    };
+    let items = ReleaseReader::<_, chunk::RawChunk>::with_limits(&bytes[..], limits)
+        .unwrap()
+        .map(|item| match item {
+            Ok(ReleaseItem::Beam(beam)) => Ok(beam.path),
+            Ok(ReleaseItem::Rel(rel)) => Ok(rel.path),
+            Err(e) => {
+                assert!(matches!(e.root_cause(), Error::LimitExceeded { .. }));
+                Err(())
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            Ok("lib/test-1.0/ebin/test.beam".to_owned()),
+            Err(()),
+            Err(()),
+            Ok("releases/1.0/foo.rel".to_owned()),
+        ],
+        items
+    );
+
+    // A corrupt `.ez` archive does not stop the following members
+    let bytes = std::fs::read(test_file("broken-1.0.tar.gz")).unwrap();
+    let items = ReleaseReader::<_, chunk::RawChunk>::new(&bytes[..])
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(2, items.len());
+    assert!(
+        matches!(items[0], Err(Error::InArchiveEntry { ref name, .. }) if name == "lib/broken-1.0.ez")
+    );
+    match items[1] {
+        Ok(ReleaseItem::Beam(ref item)) => {
+            assert_eq!("lib/test-1.0/ebin/test.beam", item.path);
+            assert_eq!(test.chunks, item.beam.chunks);
+        }
+        ref item => panic!("unexpected item: {:?}", item),
+    }
+}
+
+#[test]
+fn big_integer_operands() {
+    use beam_file::code::{self, Instruction, Operand};
+
+    let cases = [
+        (false, vec![], Operand::Integer(0)),
+        (false, vec![5], Operand::Integer(5)),
+        (true, vec![5], Operand::Integer(-5)),
+        (false, vec![0x80], Operand::Integer(0x80)),
+        (true, vec![0x80], Operand::Integer(-0x80)),
+        (false, vec![0x34, 0x12], Operand::Integer(0x1234)),
+    ];
+    for (negative, magnitude, expected) in cases {
+        let big = Operand::BigInteger {
+            negative,
+            magnitude,
+        };
+        let instruction = Instruction::new("move", vec![big, Operand::X(0)]);
+        let decoded = code::decode(&code::encode(&[instruction]).unwrap()).unwrap();
+        assert_eq!(expected, decoded[0].args[0]);
+    }
+
+    // Larger than `i64`
+    let big = Operand::BigInteger {
+        negative: true,
+        magnitude: vec![0xFF; 12],
+    };
+    let instruction = Instruction::new("move", vec![big, Operand::X(0)]);
+    let bytecode = code::encode(std::slice::from_ref(&instruction)).unwrap();
+    assert_eq!(vec![instruction], code::decode(&bytecode).unwrap());
+}
+
+#[test]
+fn disassembler() {
+    use beam_file::code;
+    use beam_file::term::Term;
+    use beam_file::Disassembler;
+
+    // `test.S` follows the listing of `erlc -S test.erl`, which differs from the disassembly
+    // in the values that `beam_asm` fills in from the MD5 of the module afterwards:
+    // the `OldUniq` operand of `make_fun2` is listed as `0` and the `vsn` attribute is not listed.
+    fn normalize(text: &str) -> String {
+        let mut normalized = String::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("{attributes, [{vsn,[") {
+                let rest = &rest[rest.find("]}").unwrap() + 2..];
+                let rest = rest.strip_prefix(',').unwrap_or(rest);
+                normalized.push_str(&format!("{{attributes, [{}\n", rest));
+            } else if let Some(rest) = line.strip_prefix("    {make_fun2,") {
+                // `{f,Label}`, `Index`, `OldUniq` and `NumFree`
+                let mut fields = rest.split(',').collect::<Vec<_>>();
+                fields[3] = "0";
+                normalized.push_str(&format!("    {{make_fun2,{}\n", fields.join(",")));
+            } else {
+                normalized.push_str(line);
+                normalized.push('\n');
+            }
+        }
+        normalized
+    }
+
+    let beam = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    let expected = std::fs::read_to_string(test_file("test.S")).unwrap();
+    let disasm = Disassembler::new(&beam).unwrap();
+    let mut buf = Vec::new();
+    disasm.to_writer(&mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    assert!(text.contains("{make_fun2,{f,8},0,38182595,1}"));
+    assert_eq!(expected, normalize(&text));
+
+    // With the source lines
+    let mut disasm = Disassembler::new(&beam).unwrap();
+    let source = std::fs::read_to_string(test_file("test.erl")).unwrap();
+    disasm.add_source("test.erl", &source);
+    let mut buf = Vec::new();
+    disasm.to_writer(&mut buf).unwrap();
+    let with_source = String::from_utf8(buf).unwrap();
+    assert!(with_source
+        .contains("%% test.erl:7: hello(Name) ->\n    {line,[{location,\"test.erl\",7}]}.\n"));
+    let without_source = with_source
+        .lines()
+        .filter(|l| !l.starts_with("%% test.erl:"))
+        .map(|l| format!("{}\n", l))
+        .collect::<String>();
+    assert_eq!(text, without_source);
+
+    // The bytecode is re-encoded as is
+    let beam = StandardBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    let bytecode = &beam.chunk::<chunk::CodeChunk>().unwrap().bytecode;
+    let instructions = code::decode(bytecode).unwrap();
+    assert_eq!(*bytecode, code::encode(&instructions).unwrap());
+
+    // Malformed instructions
+    let disasm = Disassembler::new(&beam).unwrap();
+    for instruction in [
+        code::Instruction::new(
+            "bif1",
+            vec![code::Operand::Label(0), code::Operand::Unsigned(0)],
+        ),
+        code::Instruction::new(
+            "gc_bif2",
+            vec![
+                code::Operand::Label(0),
+                code::Operand::Unsigned(1),
+                code::Operand::Unsigned(0),
+            ],
+        ),
+    ] {
+        assert!(matches!(
+            disasm.instruction(&instruction, 42),
+            Err(Error::InvalidInstruction {
+                offset: 42,
+                reason: "missing destination",
+            })
+        ));
+    }
+
+    // The offset of a malformed instruction is reported
+    let mut beam = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    beam.remove(b"FunT");
+    let mut buf = Vec::new();
+    let e = Disassembler::new(&beam)
+        .unwrap()
+        .to_writer(&mut buf)
+        .unwrap_err();
+    let Error::InvalidInstruction {
+        offset,
+        reason: "unknown fun",
+    } = e
+    else {
+        panic!("{:?}", e);
+    };
+    let beam = StandardBeamFile::from_file(test_file("test.beam")).unwrap();
+    let bytecode = &beam.chunk::<chunk::CodeChunk>().unwrap().bytecode;
+    let instructions = code::decode(&bytecode[offset..]).unwrap();
+    assert_eq!("make_fun2", instructions[0].name());
+
+    // Missing chunks
+    let beam = RawBeamFile { chunks: Vec::new() };
+    assert!(matches!(
+        Disassembler::new(&beam),
+        Err(Error::MissingChunk { id }) if &id == b"Atom"
+    ));
+
+    // Non-finite floats (e.g., in a crafted "LitT" chunk) are rejected
+    let mut bytes = vec![131, 70];
+    bytes.extend_from_slice(&f64::INFINITY.to_bits().to_be_bytes());
+    assert!(matches!(
+        Term::decode(&bytes),
+        Err(Error::InvalidTerm {
+            reason: "bad float"
+        })
+    ));
+    let mut bytes = vec![131, 99];
+    bytes.extend_from_slice(b"NaN");
+    bytes.resize(33, 0);
+    assert!(matches!(
+        Term::decode(&bytes),
+        Err(Error::InvalidTerm {
+            reason: "bad float"
+        })
+    ));
+    assert_eq!("inf", Term::Float(f64::INFINITY).to_string());
+}
+
+#[test]
+fn assembler() {
+    use beam_file::{Assembly, Disassembler};
+
+    fn disassemble<C: Chunk>(beam: &BeamFile<C>) -> String {
+        let mut buf = Vec::new();
+        Disassembler::new(beam)
+            .unwrap()
+            .to_writer(&mut buf)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+    fn atom_names(beam: &StandardBeamFile) -> Vec<String> {
+        let atoms = beam.chunk::<chunk::AtomChunk>().unwrap();
+        atoms.atoms.iter().map(|a| a.name.clone()).collect()
+    }
+
+    // The tables are built in the same order as `erlc`
+    let text = std::fs::read_to_string(test_file("test.S")).unwrap();
+    let assembly = Assembly::parse(&text).unwrap();
+    assert_eq!("test", assembly.module);
+    assert_eq!(4, assembly.functions.len());
+    let beam = assembly.assemble().unwrap();
+    let mut expected = StandardBeamFile::from_file(test_file("test.beam")).unwrap();
+    assert_eq!(atom_names(&expected), atom_names(&beam));
+    // `erlc -S` lists `OldUniq` as `0`, which `beam_asm` fills in afterwards (see `disassembler`)
+    for fun in &mut expected.chunk_mut::<chunk::FunTChunk>().unwrap().functions {
+        fun.old_uniq = 0;
+    }
+    for id in [
+        b"Code", b"StrT", b"ImpT", b"ExpT", b"FunT", b"LocT", b"Line",
+    ] {
+        assert_eq!(expected.chunk_by_id(id), beam.chunk_by_id(id));
+    }
+
+    let expected = StandardBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    let text = disassemble(&expected);
+    let beam = text.parse::<Assembly>().unwrap().assemble().unwrap();
+    assert_eq!(text, disassemble(&beam));
+    for id in [b"Code", b"ImpT", b"ExpT", b"FunT", b"LocT", b"Line"] {
+        assert_eq!(expected.chunk_by_id(id), beam.chunk_by_id(id));
+    }
+
+    // Errors
+    let header = "{module, foo}.\n{exports, []}.\n{attributes, []}.\n{labels, 2}.\n";
+    let e = Assembly::parse(&format!("{}{{function, f, 0}}", header)).unwrap_err();
+    assert!(matches!(e, Error::InvalidSyntax { line: 5, .. }));
+
+    let e = Assembly::parse("{labels, 2}.").unwrap_err();
+    assert!(matches!(e, Error::InvalidAssembly { line: 1, .. }));
+
+    let text = format!(
+        "{}{{function, f, 0, 1}}.\n{{label,1}}.\n{{no_such_op,1}}.\n",
+        header
+    );
+    let e = Assembly::parse(&text).unwrap().assemble().unwrap_err();
+    assert_eq!(
+        "f/0: entry #1 at offset 2: Error::InvalidAssemblyTerm: reason - unknown instruction",
+        e.to_string()
+    );
+}
+
+#[test]
+fn code_functions() {
+    use beam_file::code;
+
+    for name in ["test.beam", "Elixir.Unicode.beam"] {
+        let beam = RawBeamFile::from_file(test_file(name)).unwrap();
+        let functions = code::functions(&beam).unwrap();
+        let beam = beam.decode::<chunk::StandardChunk>().unwrap();
+        let code = beam.chunk::<chunk::CodeChunk>().unwrap();
+        let exports = &beam.chunk::<chunk::ExpTChunk>().unwrap().exports;
+        let locals = &beam.chunk::<chunk::LocTChunk>().unwrap().locals;
+        assert_eq!(code.function_count as usize, functions.len());
+
+        // The functions cover the bytecode except `int_code_end`
+        let mut offset = 0;
+        for function in &functions {
+            assert_eq!(offset, function.range.start);
+            assert_eq!(
+                &code.bytecode[function.range.clone()],
+                &code::encode(&function.instructions).unwrap()[..]
+            );
+            assert_eq!(function.instructions.len(), function.offsets.len());
+            offset = function.range.end;
+
+            match (function.export, function.local) {
+                (Some(i), None) => {
+                    assert_eq!(
+                        (function.arity, function.entry),
+                        (exports[i].arity, exports[i].label)
+                    )
+                }
+                (None, Some(i)) => {
+                    assert_eq!(
+                        (function.arity, function.entry),
+                        (locals[i].arity, locals[i].label)
+                    )
+                }
+                _ => panic!("not linked: {}/{}", function.name, function.arity),
+            }
+        }
+        assert_eq!(offset + 1, code.bytecode.len());
+    }
+
+    let beam = RawBeamFile { chunks: Vec::new() };
+    assert!(matches!(
+        code::functions(&beam),
+        Err(Error::MissingChunk { .. })
+    ));
+}
+
+#[test]
+fn control_flow_graph() {
+    use beam_file::cfg::{ControlFlowGraph, Edge, EdgeKind};
+    use beam_file::{code, Assembly, Disassembler};
+
+    fn edges(cfg: &ControlFlowGraph, block: usize) -> Vec<(Option<usize>, EdgeKind)> {
+        cfg.blocks[block]
+            .edges
+            .iter()
+            .map(|&Edge { target, kind }| (target, kind))
+            .collect()
+    }
+
+    let beam = StandardBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    let functions = code::functions(&beam).unwrap();
+    let info = &functions[0];
+    assert_eq!("__info__", info.name);
+    let cfg = ControlFlowGraph::new(info).unwrap();
+    assert_eq!(7, cfg.blocks.len());
+    assert_eq!(1, cfg.entry);
+    assert_eq!(
+        vec![(Some(0), EdgeKind::Fail), (Some(2), EdgeKind::Next)],
+        edges(&cfg, 1)
+    );
+    assert_eq!(
+        vec![
+            (Some(0), EdgeKind::Fail),
+            (Some(3), EdgeKind::Select),
+            (Some(4), EdgeKind::Select),
+            (Some(5), EdgeKind::Select),
+            (Some(6), EdgeKind::Select),
+        ],
+        edges(&cfg, 2)
+    );
+    assert_eq!(vec![(None, EdgeKind::Return)], edges(&cfg, 6));
+    assert_eq!(vec![1, 2], cfg.predecessors(0));
+
+    let disasm = Disassembler::new(&beam).unwrap();
+    let mut dot = Vec::new();
+    cfg.write_dot(&mut dot, info, &disasm).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph \"__info__/1\" {\n"));
+    assert!(dot.contains(
+        "\n  b5 [label=\"{label,5}\\l{move,{atom,'Elixir.Unicode'},{x,0}}\\lreturn\\l\"];\n"
+    ));
+    assert!(dot.contains("\n  b2 -> b0 [label=\"fail\"];\n  b2 -> b3 [label=\"select\"];\n"));
+    assert!(dot.ends_with("  b6 -> exit;\n}\n"));
+
+    // `try` and `receive`
+    let text = "
+{module, foo}.
+{exports, [{f,0}]}.
+{attributes, []}.
+{labels, 7}.
+
+{function, f, 0, 2}.
+  {label,1}.
+    {func_info,{atom,foo},{atom,f},0}.
+  {label,2}.
+    {allocate,1,0}.
+    {'try',{y,0},{f,5}}.
+  {label,3}.
+    {loop_rec,{f,4},{x,0}}.
+    remove_message.
+    {try_end,{y,0}}.
+    {deallocate,1}.
+    return.
+  {label,4}.
+    {wait,{f,3}}.
+  {label,5}.
+    {try_case,{y,0}}.
+    {move,{atom,error},{x,0}}.
+    {deallocate,1}.
+    return.
+";
+    let beam = text.parse::<Assembly>().unwrap().assemble().unwrap();
+    let function = &code::functions(&beam).unwrap()[0];
+    let cfg = ControlFlowGraph::new(function).unwrap();
+    assert_eq!(6, cfg.blocks.len());
+    assert_eq!(
+        vec![(Some(5), EdgeKind::Catch), (Some(2), EdgeKind::Next)],
+        edges(&cfg, 1)
+    );
+    assert_eq!(
+        vec![(Some(4), EdgeKind::Fail), (Some(3), EdgeKind::Next)],
+        edges(&cfg, 2)
+    );
+    assert_eq!(vec![(Some(2), EdgeKind::Jump)], edges(&cfg, 4));
+}
+
+#[test]
+fn register_liveness() {
+    use beam_file::cfg::ControlFlowGraph;
+    use beam_file::liveness::{HeapNeed, Liveness};
+    use beam_file::{code, Assembly};
+    use std::collections::BTreeSet;
+
+    let text = "
+{module, foo}.
+{exports, [{f,2}]}.
+{attributes, []}.
+{labels, 3}.
+
+{function, f, 2, 2}.
+  {label,1}.
+    {func_info,{atom,foo},{atom,f},2}.
+  {label,2}.
+    {allocate,2,2}.
+    {move,{x,1},{y,1}}.
+    {call_ext,1,{extfunc,erlang,display,1}}.
+    {trim,1,1}.
+    {test_heap,{alloc,[{words,2},{floats,1}]},0}.
+    {put_list,{y,0},nil,{x,0}}.
+    {call_ext_last,1,{extfunc,erlang,display,1},1}.
+";
+    let beam = text.parse::<Assembly>().unwrap().assemble().unwrap();
+    let function = &code::functions(&beam).unwrap()[0];
+    let cfg = ControlFlowGraph::new(function).unwrap();
+    let liveness = Liveness::new(function, &cfg, &[]);
+    let set = |registers: &[u32]| registers.iter().copied().collect::<BTreeSet<_>>();
+
+    assert_eq!(set(&[0, 1]), liveness.live_in[3].x);
+    assert!(liveness.live_in[3].y.is_empty());
+    assert_eq!(set(&[1]), liveness.live_out[5].y);
+    assert!(liveness.live_out[5].x.is_empty());
+    assert_eq!(set(&[1]), liveness.live_in[6].y);
+    assert_eq!(set(&[0]), liveness.live_out[6].y);
+    assert_eq!(vec![(5, set(&[1]))], liveness.live_across_calls(function));
+
+    assert_eq!(None, liveness.frame_sizes[1]);
+    assert_eq!(Some(2), liveness.frame_sizes[4]);
+    assert_eq!(Some(1), liveness.frame_sizes[7]);
+    assert_eq!(2, liveness.max_frame_size);
+    assert_eq!(
+        HeapNeed {
+            words: 2,
+            floats: 1,
+            funs: 0
+        },
+        liveness.max_heap_need
+    );
+}
+
+#[test]
+fn verifier() {
+    use beam_file::{code, verify, Assembly};
+
+    let beam = StandardBeamFile::from_file(test_file("Elixir.Unicode.beam")).unwrap();
+    assert!(verify::check(&beam).unwrap().is_empty());
+
+    let text = "
+{module, foo}.
+{exports, [{f,0},{g,1}]}.
+{attributes, []}.
+{labels, 5}.
+
+{function, f, 0, 2}.
+  {label,1}.
+    {func_info,{atom,foo},{atom,f},0}.
+  {label,2}.
+    {move,{atom,ok},{x,0}}.
+    return.
+
+{function, g, 1, 4}.
+  {label,3}.
+    {func_info,{atom,foo},{atom,g},1}.
+  {label,4}.
+    {allocate,2,1}.
+    {move,{x,0},{y,0}}.
+    {call_ext,1,{extfunc,erlang,display,1}}.
+    {move,{y,2},{x,0}}.
+    {put_list,{x,0},nil,{x,1}}.
+    {call_ext,2,{extfunc,erlang,display,1}}.
+    {call,2,{f,2}}.
+    {move,{x,1},{x,0}}.
+    return.
+";
+    let beam = text.parse::<Assembly>().unwrap().assemble().unwrap();
+    let g = &code::functions(&beam).unwrap()[1];
+    let violations = verify::check(&beam)
+        .unwrap()
+        .into_iter()
+        .map(|v| {
+            assert_eq!(("g", 1), (v.function.as_str(), v.arity));
+            let index = g.offsets.iter().position(|&o| o == v.offset).unwrap();
+            (index, v.reason)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (5, "uninitialized y register in the stack frame"),
+            (6, "y register out of the stack frame"),
+            (7, "heap not reserved"),
+            (8, "call arity mismatch"),
+            (8, "uninitialized y register in the stack frame"),
+            (9, "call arity mismatch"),
+            (9, "uninitialized x register"),
+            (9, "uninitialized y register in the stack frame"),
+            (10, "uninitialized x register"),
+            (11, "stack frame not deallocated"),
+        ],
+        violations
+    );
+    assert_eq!(
+        "g/1: offset - 45, reason - stack frame not deallocated",
+        verify::check(&beam).unwrap()[9].to_string()
+    );
+}
+
 fn test_file(name: &str) -> PathBuf {
     let mut path = PathBuf::from("tests/testdata/");
     path.push(name);
@@ -0,0 +1,77 @@
+#![cfg(feature = "serde")]
+use beam_file::chunk::{self, StandardChunk};
+use beam_file::{BeamInfo, RawBeamFile, StandardBeamFile};
+use std::path::PathBuf;
+
+#[test]
+fn json_round_trip() {
+    for name in ["test.beam", "Elixir.Unicode.beam"] {
+        let beam = StandardBeamFile::from_file(test_file(name)).unwrap();
+        let json = serde_json::to_string(&beam).unwrap();
+        let decoded: StandardBeamFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(beam.chunks, decoded.chunks);
+    }
+}
+
+#[test]
+fn json_representation() {
+    let beam = StandardBeamFile::from_file(test_file("test.beam")).unwrap();
+    let json = serde_json::to_value(&beam).unwrap();
+
+    assert_eq!(
+        serde_json::json!({"name": "test"}),
+        json["chunks"][0]["Atom"]["atoms"][0]
+    );
+    assert_eq!(
+        serde_json::json!({"module": 5, "function": 6, "arity": 1}),
+        json["chunks"][3]["ImpT"]["imports"][0]
+    );
+    assert_eq!(
+        serde_json::json!("Line"),
+        json["chunks"][11]["Unknown"]["id"]
+    );
+    let line = beam.chunk_by_id(b"Line").unwrap();
+    let StandardChunk::Unknown(ref line) = *line else {
+        panic!()
+    };
+    let hex = line
+        .data
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    assert_eq!(
+        serde_json::json!(hex),
+        json["chunks"][11]["Unknown"]["data"]
+    );
+
+    // Identifiers must be four bytes
+    let e = serde_json::from_str::<chunk::RawChunk>(r#"{"id": "Lin", "data": ""}"#);
+    assert!(e.is_err());
+    let e = serde_json::from_str::<chunk::RawChunk>(r#"{"id": "Line", "data": "0"}"#);
+    assert!(e.is_err());
+
+    // Identifiers which are not valid UTF-8 are written in hexadecimal
+    let chunk = chunk::RawChunk {
+        id: [0xFF, b'a', b'b', b'c'],
+        data: vec![1],
+    };
+    let json = serde_json::to_value(&chunk).unwrap();
+    assert_eq!(serde_json::json!("0xff616263"), json["id"]);
+    assert_eq!(chunk, serde_json::from_value(json).unwrap());
+    let e = serde_json::from_str::<chunk::RawChunk>(r#"{"id": "0xff6162", "data": ""}"#);
+    assert!(e.is_err());
+
+    let raw = RawBeamFile::from_file(test_file("test.beam")).unwrap();
+    let json = serde_json::to_value(&raw).unwrap();
+    assert_eq!(serde_json::json!("Atom"), json["chunks"][0]["id"]);
+
+    let info = BeamInfo::from_file(test_file("test.beam")).unwrap();
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(serde_json::json!("Code"), json["chunks"][1]["id"]);
+}
+
+fn test_file(name: &str) -> PathBuf {
+    let mut path = PathBuf::from("tests/testdata/");
+    path.push(name);
+    path
+}